@@ -1,104 +1,513 @@
-use std::{fs::read_to_string, collections::HashSet};
+use std::{fs::read_to_string, collections::{BTreeMap, HashSet}, fmt, process};
+use log::{debug, warn};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 struct Rucksack {
-    compartments: [HashSet<char>; 2]
+    compartments: [String; 2]
+}
+
+impl Rucksack {
+    pub fn items(&self) -> HashSet<char> {
+        self.compartments.iter().flat_map(|c| c.chars()).collect()
+    }
+}
+
+/// Three rucksacks (by index into the input) and the one item type they all carry
+#[derive(Debug, PartialEq)]
+struct BadgeGroup {
+    members: [usize; 3],
+    badge: char,
+}
+
+/// Moves every `item` out of compartment `from` into the other one, `count` moves in total
+#[derive(Debug, PartialEq)]
+struct ItemMove {
+    item: char,
+    count: usize,
+    from: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Reorganisation {
+    moves: Vec<ItemMove>,
+    result: Rucksack,
+}
+
+/// An item type found in more than one place, with how often it occurs in each of them
+#[derive(Debug, PartialEq)]
+struct SharedItem {
+    item: char,
+    counts: Vec<usize>,
 }
 
-fn prioritize(letter: char) -> u32 {
+#[derive(Debug)]
+struct Report {
+    rucksacks: Vec<Vec<SharedItem>>,
+    groups: Vec<Vec<SharedItem>>,
+}
+
+/// A problem with a single line of the input, line numbers start at 1
+#[derive(Debug, PartialEq)]
+enum InputError {
+    EmptyLine(usize),
+    OddLength(usize, usize),
+    InvalidItem(usize, char),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::EmptyLine(line) => write!(f, "line {}: empty rucksack", line),
+            InputError::OddLength(line, len) => write!(f, "line {}: {} items can't be split into two equal compartments", line, len),
+            InputError::InvalidItem(line, item) => write!(f, "line {}: '{}' is not an item, expected a-z or A-Z", line, item),
+        }
+    }
+}
+
+fn prioritize(letter: char) -> Option<u32> {
+    if !letter.is_ascii_alphabetic() {
+        return None;
+    }
     let modifier = if letter.is_ascii_uppercase() { 38 } else { 96 };
-    let letter_value = letter as u32 - modifier;
 
-    letter_value
+    Some(letter as u32 - modifier)
 }
 
-fn get_input() -> Vec<Rucksack> {
-    let file_contents = read_to_string("input.txt").unwrap();
+fn validate_input(input: &str) -> Vec<InputError> {
+    let mut errors = Vec::new();
+
+    for (idx, line) in input.lines().enumerate() {
+        let line_number = idx + 1;
+        if line.is_empty() {
+            errors.push(InputError::EmptyLine(line_number));
+            continue;
+        }
+
+        let mut item_count = 0;
+        for item in line.chars() {
+            item_count += 1;
+            if !item.is_ascii_alphabetic() {
+                errors.push(InputError::InvalidItem(line_number, item));
+            }
+        }
+        if item_count % 2 != 0 {
+            errors.push(InputError::OddLength(line_number, item_count));
+        }
+    }
+
+    errors
+}
+
+fn parse_input(input: &str) -> Result<Vec<Rucksack>, Vec<InputError>> {
+    let errors = validate_input(input);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
     let mut rucksacks = Vec::new();
 
-    for line in file_contents.split("\n") {
+    for line in input.lines() {
         let (left, right) = line.split_at(line.len() / 2);
         rucksacks.push(Rucksack {
-            compartments: [left.to_owned().chars().collect(), right.to_owned().chars().collect()],
+            compartments: [left.to_owned(), right.to_owned()],
         });
     }
 
-    rucksacks
+    Ok(rucksacks)
 }
 
-fn get_common_letter(rucksack: &Rucksack) -> char {
-    let mut possible_common_item: Option<char> = None;
+fn get_input() -> Vec<Rucksack> {
+    match parse_input(&read_to_string("input.txt").unwrap()) {
+        Ok(rucksacks) => rucksacks,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            process::exit(1);
+        },
+    }
+}
 
-    for item in &rucksack.compartments[0] {
-        if rucksack.compartments[1].contains(item) {
-            possible_common_item = Some(*item);
-            break;
-        }
+/// Every item type that occurs in all of `contents`, sorted so the output doesn't depend on
+/// `HashSet` iteration order
+fn get_shared_items(contents: &[&str]) -> Vec<SharedItem> {
+    let mut shared: Vec<char> = match contents.first() {
+        Some(first) => first.chars().collect::<HashSet<char>>().into_iter().collect(),
+        None => return Vec::new(),
+    };
+    shared.retain(|item| contents.iter().all(|c| c.contains(*item)));
+    shared.sort();
+
+    shared.into_iter().map(|item| SharedItem {
+        item,
+        counts: contents.iter().map(|c| c.chars().filter(|l| *l == item).count()).collect(),
+    }).collect()
+}
+
+fn get_rucksack_shared_items(rucksack: &Rucksack) -> Vec<SharedItem> {
+    get_shared_items(&[&rucksack.compartments[0], &rucksack.compartments[1]])
+}
+
+fn get_group_shared_items(group: &[Rucksack]) -> Vec<SharedItem> {
+    let contents: Vec<String> = group.iter().map(|r| r.compartments.concat()).collect();
+
+    get_shared_items(&contents.iter().map(|c| c.as_str()).collect::<Vec<&str>>())
+}
+
+fn get_only_item(shared: Vec<SharedItem>) -> Option<char> {
+    if shared.len() == 1 {
+        return Some(shared[0].item);
     }
-    
-    if let Some(common_letter) = possible_common_item {
-        return common_letter;
+    None
+}
+
+fn get_common_letter(rucksack: &Rucksack) -> Option<char> {
+    get_only_item(get_rucksack_shared_items(rucksack))
+}
+
+fn get_groups(rucksacks: &[Rucksack]) -> Vec<&[Rucksack]> {
+    rucksacks.chunks(3).filter(|batch| batch.len() == 3).collect()
+}
+
+fn get_group_badge(group: &[Rucksack]) -> Option<char> {
+    get_only_item(get_group_shared_items(group))
+}
+
+fn get_report(rucksacks: &[Rucksack]) -> Report {
+    Report {
+        rucksacks: rucksacks.iter().map(get_rucksack_shared_items).collect(),
+        groups: get_groups(rucksacks).into_iter().map(get_group_shared_items).collect(),
     }
-    panic!("Appareanlty couldn't assue there was a smilarity.\nrucksack: {:?}", rucksack);
 }
 
-fn part1(rucksacks: &Vec<Rucksack>) -> u32 {
-    let mut priorities_sum = 0;
+fn format_shared_items(shared: &[SharedItem]) -> String {
+    shared.iter()
+        .map(|s| format!("{} ({})", s.item, s.counts.iter().map(|c| c.to_string()).collect::<Vec<String>>().join("/")))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
 
-    for rucksack in rucksacks {
-        let common_item = get_common_letter(rucksack);
-        let prioritized_common_item = prioritize(common_item);
-        priorities_sum += prioritized_common_item;
+fn print_report(report: &Report) {
+    println!("Rucksacks:");
+    for (idx, shared) in report.rucksacks.iter().enumerate() {
+        let flag = if shared.len() == 1 { "" } else { "  <-- expected exactly one shared item" };
+        println!("  {:>4}: {}{}", idx + 1, format_shared_items(shared), flag);
+    }
+
+    println!("Groups:");
+    for (idx, shared) in report.groups.iter().enumerate() {
+        let flag = if shared.len() == 1 { "" } else { "  <-- expected exactly one badge" };
+        println!("  {:>4}: {}{}", idx + 1, format_shared_items(shared), flag);
     }
-    
-    priorities_sum
 }
 
-fn part2(rucksacks: &Vec<Rucksack>) -> u32 {
-    let mut priorities_sum = 0;
+/// One bit per item type, bit `n` is the item with priority `n`
+fn get_item_mask(items: &HashSet<char>) -> u64 {
+    items.iter().filter_map(|item| prioritize(*item)).fold(0, |mask, priority| mask | 1 << priority)
+}
 
-    let mut idx = 0;
-    loop {
-        let batch: Vec<&Rucksack> = rucksacks.iter().skip(idx * 3).take(3).collect();
-        if batch.len() != 3 {
-            break;
+fn search_badge_groups(candidates: &[[usize; 3]], by_rucksack: &[Vec<usize>], used: &mut [bool], chosen: &mut Vec<usize>) -> bool {
+    // Branch on the rucksack with the fewest groups left to join, if that's zero this branch is dead
+    let mut most_constrained: Option<(usize, usize)> = None;
+    for (rucksack, candidate_idxs) in by_rucksack.iter().enumerate() {
+        if used[rucksack] { continue }
+        let options = candidate_idxs.iter()
+            .filter(|c| candidates[**c].iter().all(|member| !used[*member]))
+            .count();
+        if most_constrained.is_none_or(|(_, fewest)| options < fewest) {
+            most_constrained = Some((rucksack, options));
         }
-        idx += 1;
+    }
 
-        let rucksack_sets: Vec<HashSet<char>> = batch.into_iter().map(|r| {
-            let mut set = HashSet::new();
+    let rucksack = match most_constrained {
+        None => return true,
+        Some((_, 0)) => return false,
+        Some((rucksack, _)) => rucksack,
+    };
 
-            set.extend(r.compartments[0].clone());
-            set.extend(r.compartments[1].clone());
+    for candidate_idx in by_rucksack[rucksack].iter() {
+        let members = candidates[*candidate_idx];
+        if members.iter().any(|member| used[*member]) { continue }
 
-            println!("{:?} {:?}", r, set);
-            set
-        }).collect();
+        members.iter().for_each(|member| used[*member] = true);
+        chosen.push(*candidate_idx);
+        if search_badge_groups(candidates, by_rucksack, used, chosen) {
+            return true;
+        }
+        chosen.pop();
+        members.iter().for_each(|member| used[*member] = false);
+    }
 
-        println!("");
-        println!("{:?}", rucksack_sets);
+    false
+}
 
-        let mut common_letter = None;
-        for letter in rucksack_sets[0].iter() {
-            if rucksack_sets[1].contains(letter) && rucksack_sets[2].contains(letter) {
-                common_letter = Some(letter);
+/// Partitions the rucksacks into groups of three that share exactly one item type, regardless of
+/// the order they're in. Returns `None` when no such partition exists.
+fn find_badge_groups(rucksacks: &[Rucksack]) -> Option<Vec<BadgeGroup>> {
+    if !rucksacks.len().is_multiple_of(3) {
+        return None;
+    }
+
+    let masks: Vec<u64> = rucksacks.iter().map(|r| get_item_mask(&r.items())).collect();
+    let mut candidates: Vec<[usize; 3]> = Vec::new();
+    let mut by_rucksack: Vec<Vec<usize>> = vec![Vec::new(); rucksacks.len()];
+
+    for a in 0..masks.len() {
+        for b in a + 1..masks.len() {
+            let shared = masks[a] & masks[b];
+            if shared == 0 { continue }
+            for (c, mask) in masks.iter().enumerate().skip(b + 1) {
+                if (shared & mask).count_ones() == 1 {
+                    for member in [a, b, c] {
+                        by_rucksack[member].push(candidates.len());
+                    }
+                    candidates.push([a, b, c]);
+                }
             }
         }
+    }
+
+    debug!("{} candidate badge groups for {} rucksacks", candidates.len(), rucksacks.len());
+
+    let mut used = vec![false; rucksacks.len()];
+    let mut chosen = Vec::new();
+    if !search_badge_groups(&candidates, &by_rucksack, &mut used, &mut chosen) {
+        return None;
+    }
+
+    Some(chosen.into_iter().map(|candidate_idx| {
+        let members = candidates[candidate_idx];
+        let badge_priority = members.iter().fold(u64::MAX, |mask, m| mask & masks[*m]).trailing_zeros();
+        BadgeGroup {
+            members,
+            badge: rucksacks[members[0]].items().into_iter().find(|item| prioritize(*item) == Some(badge_priority)).unwrap(),
+        }
+    }).collect())
+}
 
-        if let Some(common) = common_letter {
-            priorities_sum += prioritize(*common);
+fn print_badge_groups(groups: &Option<Vec<BadgeGroup>>) {
+    match groups {
+        Some(groups) => {
+            for group in groups.iter() {
+                let members: Vec<String> = group.members.iter().map(|m| (m + 1).to_string()).collect();
+                println!("{}: {}", group.badge, members.join(", "));
+            }
+        },
+        None => println!("No partition into badge groups exists"),
+    }
+}
+
+/// Plans the fewest item moves that leave no item type in both compartments of `rucksack`.
+/// Every shared item type has to be gathered on one side, so each one is a choice between moving
+/// its left copies right or its right copies left. Among the cheapest plans the one with the most
+/// evenly sized compartments wins.
+fn plan_reorganisation(rucksack: &Rucksack) -> Reorganisation {
+    let shared = get_rucksack_shared_items(rucksack);
+
+    // Cheapest way to reach each change in left compartment size, with the choices that got there
+    let mut plans: BTreeMap<i64, (usize, Vec<usize>)> = BTreeMap::new();
+    plans.insert(0, (0, Vec::new()));
+    for shared_item in shared.iter() {
+        let mut next_plans: BTreeMap<i64, (usize, Vec<usize>)> = BTreeMap::new();
+        for (delta, (cost, choices)) in plans.iter() {
+            for from in 0..2 {
+                let count = shared_item.counts[from];
+                let next_delta = if from == 0 { delta - count as i64 } else { delta + count as i64 };
+                let next_cost = cost + count;
+                if next_plans.get(&next_delta).is_none_or(|(best, _)| next_cost < *best) {
+                    let mut next_choices = choices.clone();
+                    next_choices.push(from);
+                    next_plans.insert(next_delta, (next_cost, next_choices));
+                }
+            }
         }
+        plans = next_plans;
     }
 
+    let size_difference = rucksack.compartments[0].len() as i64 - rucksack.compartments[1].len() as i64;
+    let (_, (_, choices)) = plans.into_iter()
+        .min_by_key(|(delta, (cost, _))| (*cost, (size_difference + 2 * delta).abs()))
+        .unwrap();
+
+    let moves: Vec<ItemMove> = shared.iter().zip(choices).map(|(shared_item, from)| ItemMove {
+        item: shared_item.item,
+        count: shared_item.counts[from],
+        from,
+    }).collect();
+
+    let mut compartments = rucksack.compartments.clone();
+    for item_move in moves.iter() {
+        let to = 1 - item_move.from;
+        compartments[item_move.from].retain(|item| item != item_move.item);
+        compartments[to].extend(std::iter::repeat_n(item_move.item, item_move.count));
+    }
+
+    Reorganisation {
+        moves,
+        result: Rucksack { compartments },
+    }
+}
+
+fn print_reorganisations(rucksacks: &[Rucksack]) {
+    let mut total_moves = 0;
+
+    for (idx, rucksack) in rucksacks.iter().enumerate() {
+        let reorganisation = plan_reorganisation(rucksack);
+        let moves: Vec<String> = reorganisation.moves.iter()
+            .map(|m| format!("{}x {} {}", m.count, m.item, if m.from == 0 { "->" } else { "<-" }))
+            .collect();
+        total_moves += reorganisation.moves.iter().map(|m| m.count).sum::<usize>();
+
+        println!(
+            "{:>4}: {:<24} {} | {}",
+            idx + 1,
+            moves.join(", "),
+            reorganisation.result.compartments[0],
+            reorganisation.result.compartments[1],
+        );
+    }
+
+    println!("Total moves: {}", total_moves);
+}
+
+fn part1(rucksacks: &[Rucksack]) -> u32 {
+    let mut priorities_sum = 0;
+
+    for (idx, rucksack) in rucksacks.iter().enumerate() {
+        match get_common_letter(rucksack).and_then(prioritize) {
+            Some(priority) => priorities_sum += priority,
+            None => warn!("rucksack {}: expected exactly one shared item, left out of the total", idx + 1),
+        }
+    }
+    
     priorities_sum
 }
 
+fn part2(rucksacks: &[Rucksack]) -> u32 {
+    let mut priorities_sum = 0;
+
+    for (idx, group) in get_groups(rucksacks).into_iter().enumerate() {
+        for rucksack in group.iter() {
+            debug!("{:?} {:?}", rucksack, rucksack.items());
+        }
+        debug!("badge candidates: {:?}", get_group_shared_items(group));
+
+        match get_group_badge(group).and_then(prioritize) {
+            Some(priority) => priorities_sum += priority,
+            None => warn!("group {}: expected exactly one badge, left out of the total", idx + 1),
+        }
+    }
+
+    priorities_sum
+}
+
+/// Diagnostics go to stderr through `log`, `--verbose` turns on debug output unless `RUST_LOG` says otherwise
+fn init_logging(args: &[String]) {
+    let default_level = if args.iter().any(|a| a == "--verbose" || a == "-v") { "debug" } else { "warn" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level)).init();
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    init_logging(&args);
     let rucksacks = get_input();
 
+    if args.iter().any(|a| a == "report") {
+        print_report(&get_report(&rucksacks));
+        return;
+    }
+    if args.iter().any(|a| a == "groups") {
+        print_badge_groups(&find_badge_groups(&rucksacks));
+        return;
+    }
+    if args.iter().any(|a| a == "plan") {
+        print_reorganisations(&rucksacks);
+        return;
+    }
+
     let part1_result = part1(&rucksacks);
     println!("Part 1: {}", part1_result);
 
     let part2_result = part2(&rucksacks);
     println!("Part 2: {}", part2_result);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    #[test]
+    fn test_example() {
+        let rucksacks = parse_input(EXAMPLE).unwrap();
+        assert_eq!(part1(&rucksacks), 157);
+        assert_eq!(part2(&rucksacks), 70);
+    }
+
+    #[test]
+    fn test_report_flags_ambiguous_rucksacks() {
+        let rucksacks = parse_input("abcabd\nabcdef").unwrap();
+        let report = get_report(&rucksacks);
+
+        assert_eq!(report.rucksacks[0], vec![
+            SharedItem { item: 'a', counts: vec![1, 1] },
+            SharedItem { item: 'b', counts: vec![1, 1] },
+        ]);
+        assert!(report.rucksacks[1].is_empty());
+        assert_eq!(get_common_letter(&rucksacks[0]), None);
+        assert_eq!(part1(&rucksacks), 0);
+    }
+
+    #[test]
+    fn test_validation() {
+        let errors = parse_input("abcabd\n\nab1\nabc").unwrap_err();
+
+        assert_eq!(errors, vec![
+            InputError::EmptyLine(2),
+            InputError::InvalidItem(3, '1'),
+            InputError::OddLength(3, 3),
+            InputError::OddLength(4, 3),
+        ]);
+    }
+
+    #[test]
+    fn test_find_badge_groups_in_shuffled_input() {
+        let lines: Vec<&str> = EXAMPLE.lines().collect();
+        let shuffled = [lines[3], lines[0], lines[5], lines[1], lines[4], lines[2]].join("\n");
+        let rucksacks = parse_input(&shuffled).unwrap();
+
+        let groups = find_badge_groups(&rucksacks).unwrap();
+        assert_eq!(groups, vec![
+            BadgeGroup { members: [0, 2, 4], badge: 'Z' },
+            BadgeGroup { members: [1, 3, 5], badge: 'r' },
+        ]);
+    }
+
+    #[test]
+    fn test_find_badge_groups_without_partition() {
+        let rucksacks = parse_input("abcd\nabef\nabgh\nxyzw").unwrap();
+        assert_eq!(find_badge_groups(&rucksacks), None);
+
+        let rucksacks = parse_input("ax\nay\naz\nbx\nby\nqq").unwrap();
+        assert_eq!(find_badge_groups(&rucksacks), None);
+    }
+
+    #[test]
+    fn test_plan_reorganisation() {
+        let rucksack = &parse_input("aabcabdd").unwrap()[0];
+        let reorganisation = plan_reorganisation(rucksack);
+
+        assert_eq!(reorganisation.moves, vec![
+            ItemMove { item: 'a', count: 1, from: 1 },
+            ItemMove { item: 'b', count: 1, from: 0 },
+        ]);
+        assert_eq!(reorganisation.result.compartments, ["aaca".to_string(), "bddb".to_string()]);
+        assert!(get_rucksack_shared_items(&reorganisation.result).is_empty());
+    }
+}
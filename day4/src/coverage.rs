@@ -0,0 +1,104 @@
+use day4::interval::Interval;
+
+/// How many assignments cover each section, as runs of sections with the same depth. The runs are
+/// in order and together span everything from the lowest assigned section to the highest one.
+#[derive(Debug, PartialEq)]
+pub struct Coverage {
+    pub profile: Vec<(Interval, u32)>,
+    pub max_depth: u32,
+}
+
+impl Coverage {
+    /// Sweeps over the start and end of every assignment, so this is `O(n log n)` in the number of
+    /// assignments no matter how many sections they span.
+    pub fn analyse<'a>(assignments: impl IntoIterator<Item = &'a Interval>) -> Self {
+        // (section, change in depth), ends are stored one past the last section so they sort after
+        // starts on the same section
+        let mut events: Vec<(u64, i64)> = Vec::new();
+        for assignment in assignments {
+            events.push((assignment.start as u64, 1));
+            events.push((assignment.end as u64 + 1, -1));
+        }
+        events.sort_unstable();
+
+        let mut profile: Vec<(Interval, u32)> = Vec::new();
+        let mut depth: i64 = 0;
+        let mut idx = 0;
+        while idx < events.len() {
+            let section = events[idx].0;
+            while idx < events.len() && events[idx].0 == section {
+                depth += events[idx].1;
+                idx += 1;
+            }
+
+            if let Some(&(next_section, _)) = events.get(idx) {
+                let run = Interval::new(section as u32, (next_section - 1) as u32);
+                match profile.last_mut() {
+                    Some((last, last_depth)) if *last_depth == depth as u32 => last.end = run.end,
+                    _ => profile.push((run, depth as u32)),
+                }
+            }
+        }
+
+        Coverage {
+            max_depth: profile.iter().map(|(_, depth)| *depth).max().unwrap_or(0),
+            profile,
+        }
+    }
+
+    /// The runs of sections covered by the most assignments
+    pub fn deepest(&self) -> Vec<Interval> {
+        self.runs_with_depth(self.max_depth)
+    }
+
+    /// Sections between the first and last assignment that nobody covers
+    pub fn uncovered(&self) -> Vec<Interval> {
+        self.runs_with_depth(0)
+    }
+
+    fn runs_with_depth(&self, depth: u32) -> Vec<Interval> {
+        self.profile.iter()
+            .filter(|(_, d)| *d == depth)
+            .map(|(run, _)| *run)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let assignments = [
+            Interval::new(2, 4), Interval::new(6, 8),
+            Interval::new(2, 3), Interval::new(4, 5),
+            Interval::new(5, 7), Interval::new(7, 9),
+            Interval::new(2, 8), Interval::new(3, 7),
+            Interval::new(6, 6), Interval::new(4, 6),
+            Interval::new(2, 6), Interval::new(4, 8),
+        ];
+        let coverage = Coverage::analyse(assignments.iter());
+
+        assert_eq!(coverage.max_depth, 8);
+        assert_eq!(coverage.deepest(), vec![Interval::new(6, 6)]);
+        assert!(coverage.uncovered().is_empty());
+        assert_eq!(coverage.profile.first(), Some(&(Interval::new(2, 2), 4)));
+        assert_eq!(coverage.profile.last(), Some(&(Interval::new(9, 9), 1)));
+    }
+
+    #[test]
+    fn test_gaps_and_extremes() {
+        let assignments = [Interval::new(1, 2), Interval::new(5, 5), Interval::new(5, u32::MAX)];
+        let coverage = Coverage::analyse(assignments.iter());
+
+        assert_eq!(coverage.profile, vec![
+            (Interval::new(1, 2), 1),
+            (Interval::new(3, 4), 0),
+            (Interval::new(5, 5), 2),
+            (Interval::new(6, u32::MAX), 1),
+        ]);
+        assert_eq!(coverage.uncovered(), vec![Interval::new(3, 4)]);
+        assert_eq!(Coverage::analyse([].iter()).max_depth, 0);
+    }
+}
@@ -0,0 +1,102 @@
+use day4::interval::Interval;
+
+/// A static interval tree. The entries are sorted by interval and the tree is implicit in that
+/// order: the middle of any range of entries is the root of the subtree for that range. Each
+/// root also remembers the highest end in its subtree, which lets queries skip whole subtrees
+/// that end before the range asked about, so a query is `O(log n + k)` for `k` results.
+#[derive(Debug)]
+pub struct IntervalIndex<T> {
+    entries: Vec<(Interval, T)>,
+    max_ends: Vec<u32>,
+}
+
+impl<T: Copy> IntervalIndex<T> {
+    pub fn new(mut entries: Vec<(Interval, T)>) -> Self {
+        entries.sort_by_key(|(interval, _)| *interval);
+        let mut max_ends = vec![0; entries.len()];
+        Self::build(&entries, &mut max_ends, 0, entries.len());
+
+        IntervalIndex {
+            entries,
+            max_ends,
+        }
+    }
+
+    fn build(entries: &[(Interval, T)], max_ends: &mut [u32], lo: usize, hi: usize) -> u32 {
+        if lo >= hi {
+            return 0;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let left = Self::build(entries, max_ends, lo, mid);
+        let right = Self::build(entries, max_ends, mid + 1, hi);
+        max_ends[mid] = entries[mid].0.end.max(left).max(right);
+
+        max_ends[mid]
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Every entry whose interval covers `section`
+    pub fn covering(&self, section: u32) -> Vec<(Interval, T)> {
+        self.overlapping(&Interval::new(section, section))
+    }
+
+    /// Every entry whose interval shares at least one section with `range`, ordered by interval
+    pub fn overlapping(&self, range: &Interval) -> Vec<(Interval, T)> {
+        let mut found = Vec::new();
+        self.search(range, 0, self.entries.len(), &mut found);
+
+        found
+    }
+
+    fn search(&self, range: &Interval, lo: usize, hi: usize, found: &mut Vec<(Interval, T)>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if self.max_ends[mid] < range.start {
+            return;
+        }
+
+        self.search(range, lo, mid, found);
+        let (interval, value) = self.entries[mid];
+        // Everything to the right starts at or after this entry, so it can't overlap either
+        if interval.start > range.end {
+            return;
+        }
+        if interval.overlaps(range) {
+            found.push((interval, value));
+        }
+        self.search(range, mid + 1, hi, found);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn overlapping_matches_linear_scan(
+            intervals in prop::collection::vec((0u32..100, 0u32..20), 0..60),
+            start in 0u32..120,
+            len in 0u32..20,
+        ) {
+            let entries: Vec<(Interval, usize)> = intervals.into_iter()
+                .enumerate()
+                .map(|(idx, (s, l))| (Interval::new(s, s + l), idx))
+                .collect();
+            let range = Interval::new(start, start + len);
+
+            let mut expected: Vec<(Interval, usize)> = entries.iter().filter(|(i, _)| i.overlaps(&range)).copied().collect();
+            expected.sort();
+            let mut actual = IntervalIndex::new(entries).overlapping(&range);
+            actual.sort();
+
+            prop_assert_eq!(actual, expected);
+        }
+    }
+}
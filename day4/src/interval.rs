@@ -0,0 +1,189 @@
+
+use std::fmt;
+
+/// An inclusive range of sections, `start..=end`. Never empty, `start <= end` always holds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    pub start: u32,
+    pub end: u32,
+}
+
+// An interval always holds at least one section, so there's no `is_empty` to go with `len`
+#[allow(clippy::len_without_is_empty)]
+impl Interval {
+    pub fn new(start: u32, end: u32) -> Self {
+        assert!(start <= end, "Interval start {} is after its end {}", start, end);
+
+        Interval {
+            start,
+            end,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        (self.end - self.start) as u64 + 1
+    }
+
+    pub fn contains_value(&self, value: u32) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    /// Whether `other` lies completely inside this interval
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        Some(Interval::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+
+    /// The smallest interval covering both, or `None` if there's a gap between them
+    pub fn union(&self, other: &Interval) -> Option<Interval> {
+        let (first, second) = if self <= other { (self, other) } else { (other, self) };
+        if first.end < u32::MAX && first.end + 1 < second.start {
+            return None;
+        }
+
+        Some(Interval::new(first.start, first.end.max(second.end)))
+    }
+
+    /// The parts of this interval not covered by `other`, in order. Zero, one or two intervals.
+    pub fn difference(&self, other: &Interval) -> Vec<Interval> {
+        let mut parts = Vec::new();
+        if !self.overlaps(other) {
+            parts.push(*self);
+            return parts;
+        }
+
+        if self.start < other.start {
+            parts.push(Interval::new(self.start, other.start - 1));
+        }
+        if other.end < self.end {
+            parts.push(Interval::new(other.end + 1, self.end));
+        }
+
+        parts
+    }
+}
+
+/// Combines the intervals into the fewest disjoint ones covering the same sections, in order
+pub fn merge(intervals: &[Interval]) -> Vec<Interval> {
+    let mut sorted = intervals.to_vec();
+    sorted.sort();
+
+    let mut merged: Vec<Interval> = Vec::new();
+    for interval in sorted.into_iter() {
+        match merged.last_mut().and_then(|last| last.union(&interval).map(|union| (last, union))) {
+            Some((last, union)) => *last = union,
+            None => merged.push(interval),
+        }
+    }
+
+    merged
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn interval() -> impl Strategy<Value = Interval> {
+        (0u32..200, 0u32..50).prop_map(|(start, len)| Interval::new(start, start + len))
+    }
+
+    fn values(interval: &Interval) -> Vec<u32> {
+        (interval.start..=interval.end).collect()
+    }
+
+    #[test]
+    fn test_examples() {
+        let a = Interval::new(2, 8);
+        let b = Interval::new(3, 7);
+
+        assert!(a.contains(&b));
+        assert!(!b.contains(&a));
+        assert!(Interval::new(5, 7).overlaps(&Interval::new(7, 9)));
+        assert!(!Interval::new(2, 4).overlaps(&Interval::new(6, 8)));
+        assert_eq!(Interval::new(2, 4).union(&Interval::new(5, 8)), Some(Interval::new(2, 8)));
+        assert_eq!(Interval::new(2, 4).union(&Interval::new(6, 8)), None);
+        assert_eq!(a.difference(&b), vec![Interval::new(2, 2), Interval::new(8, 8)]);
+        assert_eq!(Interval::new(0, u32::MAX).len(), 1 << 32);
+    }
+
+    proptest! {
+        #[test]
+        fn len_counts_values(a in interval()) {
+            prop_assert_eq!(a.len(), values(&a).len() as u64);
+        }
+
+        #[test]
+        fn contains_matches_values(a in interval(), b in interval()) {
+            let expected = values(&b).iter().all(|v| a.contains_value(*v));
+            prop_assert_eq!(a.contains(&b), expected);
+        }
+
+        #[test]
+        fn overlaps_matches_values(a in interval(), b in interval()) {
+            let expected = values(&a).iter().any(|v| b.contains_value(*v));
+            prop_assert_eq!(a.overlaps(&b), expected);
+            prop_assert_eq!(a.overlaps(&b), b.overlaps(&a));
+        }
+
+        #[test]
+        fn intersection_matches_values(a in interval(), b in interval()) {
+            let expected: Vec<u32> = values(&a).into_iter().filter(|v| b.contains_value(*v)).collect();
+            let actual = a.intersection(&b).map(|i| values(&i)).unwrap_or_default();
+            prop_assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn union_matches_values(a in interval(), b in interval()) {
+            let mut expected: Vec<u32> = values(&a).into_iter().chain(values(&b)).collect();
+            expected.sort();
+            expected.dedup();
+            let contiguous = expected.windows(2).all(|w| w[0] + 1 == w[1]);
+
+            match a.union(&b) {
+                Some(union) => prop_assert_eq!(values(&union), expected),
+                None => prop_assert!(!contiguous),
+            }
+        }
+
+        #[test]
+        fn difference_matches_values(a in interval(), b in interval()) {
+            let expected: Vec<u32> = values(&a).into_iter().filter(|v| !b.contains_value(*v)).collect();
+            let actual: Vec<u32> = a.difference(&b).iter().flat_map(values).collect();
+            prop_assert_eq!(actual, expected);
+        }
+
+        #[test]
+        fn merge_matches_values(intervals in prop::collection::vec(interval(), 0..8)) {
+            let mut expected: Vec<u32> = intervals.iter().flat_map(values).collect();
+            expected.sort();
+            expected.dedup();
+
+            let merged = merge(&intervals);
+            prop_assert_eq!(merged.iter().flat_map(values).collect::<Vec<u32>>(), expected);
+            prop_assert!(merged.windows(2).all(|w| w[0].union(&w[1]).is_none()));
+        }
+
+        #[test]
+        fn ordering_is_by_start_then_end(a in interval(), b in interval()) {
+            prop_assert_eq!(a.cmp(&b), (a.start, a.end).cmp(&(b.start, b.end)));
+        }
+    }
+}
@@ -0,0 +1,3 @@
+//! The interval type day 4 is built on, as a library so other interval-shaped puzzles can use it
+
+pub mod interval;
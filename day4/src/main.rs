@@ -1,75 +1,332 @@
-use std::fs::read_to_string;
+mod coverage;
+mod index;
+mod reassign;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Sections {
-    start: u32,
-    end: u32,
+use std::{fs::read_to_string, io::{self, BufRead}, fmt, process};
+use coverage::Coverage;
+use index::IntervalIndex;
+use day4::interval::{self, Interval};
+
+/// The sections of every elf in one group, in the order they're listed on the line
+type Assignments = Vec<Interval>;
+
+#[derive(Debug, PartialEq)]
+struct GroupReport {
+    contained: Vec<usize>,
+    overlapping: bool,
+    coverage: Vec<Interval>,
+}
+
+/// A problem with one line of the input, line numbers start at 1
+#[derive(Debug, PartialEq)]
+enum InputError {
+    /// The line and which assignment on it is blank, counting from 1
+    EmptyField(usize, usize),
+    MissingDash(usize, String),
+    BoundCount(usize, String),
+    InvalidBound(usize, String),
+    ReversedRange(usize, String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::EmptyField(line, field) => write!(f, "line {}: assignment {} is empty", line, field),
+            InputError::MissingDash(line, field) => write!(f, "line {}: '{}' is missing the '-' between start and end", line, field),
+            InputError::BoundCount(line, field) => write!(f, "line {}: '{}' should have exactly one start and one end", line, field),
+            InputError::InvalidBound(line, bound) => write!(f, "line {}: '{}' is not a section number", line, bound),
+            InputError::ReversedRange(line, field) => write!(f, "line {}: '{}' ends before it starts", line, field),
+        }
+    }
 }
 
-type Assignments = (Sections, Sections);
+/// Parses `start-end`. A reversed range is an error, unless `normalise` is set in which case its
+/// bounds are swapped.
+fn parse_sections(value: &str, line_number: usize, normalise: bool) -> Result<Interval, InputError> {
+    let nums_in_value: Vec<&str> = value.split("-").collect();
+    if nums_in_value.len() == 1 {
+        return Err(InputError::MissingDash(line_number, value.to_string()));
+    }
+    if nums_in_value.len() != 2 {
+        return Err(InputError::BoundCount(line_number, value.to_string()));
+    }
 
-impl From<&str> for Sections {
-    fn from(value: &str) -> Self {
-        let nums_in_value: Vec<&str> = value.split("-").collect();
-        let start = nums_in_value[0].parse().unwrap();
-        let end = nums_in_value[1].parse().unwrap();
+    let parse_bound = |bound: &str| bound.trim().parse::<u32>()
+        .map_err(|_| InputError::InvalidBound(line_number, bound.to_string()));
+    let start = parse_bound(nums_in_value[0])?;
+    let end = parse_bound(nums_in_value[1])?;
 
-        Sections {
-            start,
-            end,
+    if start > end {
+        if !normalise {
+            return Err(InputError::ReversedRange(line_number, value.to_string()));
         }
+        return Ok(Interval::new(end, start));
     }
+
+    Ok(Interval::new(start, end))
 }
 
-fn get_input() -> Vec<Assignments> {
-    let file_contents = read_to_string("input.txt").unwrap();
-    let mut input = Vec::new();
+fn parse_input(input: &str, normalise: bool) -> Result<Vec<Assignments>, Vec<InputError>> {
+    let mut assignments = Vec::new();
+    let mut errors = Vec::new();
 
-    for line in file_contents.split("\n") {
+    for (idx, line) in input.lines().enumerate() {
         if line.is_empty() { continue }
-        let pairs_in_line: Vec<&str> = line.split(",").collect();
+        let line_number = idx + 1;
 
-        input.push((Sections::from(pairs_in_line[0]), Sections::from(pairs_in_line[1])));
+        let mut group = Vec::new();
+        for (field_idx, field) in line.split(",").enumerate() {
+            if field.trim().is_empty() {
+                errors.push(InputError::EmptyField(line_number, field_idx + 1));
+                continue;
+            }
+            match parse_sections(field, line_number, normalise) {
+                Ok(sections) => group.push(sections),
+                Err(error) => errors.push(error),
+            }
+        }
+        assignments.push(group);
     }
 
-    input
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(assignments)
 }
 
-fn in_between(value: u32, start: u32, end: u32) -> bool {
-    start <= value && end >= value
+fn get_input(normalise: bool) -> Vec<Assignments> {
+    match parse_input(&read_to_string("input.txt").unwrap(), normalise) {
+        Ok(input) => input,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            process::exit(1);
+        },
+    }
 }
 
-fn part2(input: Vec<Assignments>) -> u32 {
-    let mut overlaps = 0;
+/// Indices of the elves whose sections are all also assigned to a single other elf in the group
+fn get_contained_members(group: &[Interval]) -> Vec<usize> {
+    (0..group.len())
+        .filter(|i| (0..group.len()).any(|j| *i != j && group[j].contains(&group[*i])))
+        .collect()
+}
 
-    for assignment in input.into_iter() {
-        if in_between(assignment.0.start, assignment.1.start, assignment.1.end)
-            || in_between(assignment.0.end, assignment.1.start, assignment.1.end)
-            || in_between(assignment.1.start, assignment.0.start, assignment.0.end)
-            || in_between(assignment.1.end, assignment.0.start, assignment.0.end) {
-            overlaps += 1;
-        }
+/// Whether any two elves in the group share a section. Once sorted, if any two overlap then two
+/// neighbours do as well.
+fn has_overlap(group: &[Interval]) -> bool {
+    let mut sorted = group.to_vec();
+    sorted.sort();
+
+    sorted.windows(2).any(|w| w[0].overlaps(&w[1]))
+}
+
+fn get_group_report(group: &[Interval]) -> GroupReport {
+    GroupReport {
+        contained: get_contained_members(group),
+        overlapping: has_overlap(group),
+        coverage: interval::merge(group),
+    }
+}
+
+fn part2(input: &[Assignments]) -> u32 {
+    input.iter().filter(|group| has_overlap(group)).count() as u32
+}
+
+fn part1(input: &[Assignments]) -> u32 {
+    input.iter().filter(|group| !get_contained_members(group).is_empty()).count() as u32
+}
+
+fn format_intervals(intervals: &[Interval]) -> String {
+    if intervals.is_empty() {
+        return "none".to_string();
+    }
+    intervals.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(", ")
+}
+
+fn print_coverage(input: &[Assignments]) {
+    let coverage = Coverage::analyse(input.iter().flatten());
+
+    println!("Most elves on one section: {}", coverage.max_depth);
+    println!("Sections with that many: {}", format_intervals(&coverage.deepest()));
+    println!("Uncovered sections: {}", format_intervals(&coverage.uncovered()));
+    println!("Coverage:");
+    for (run, depth) in coverage.profile.iter() {
+        println!("  {:>9}: {}", run.to_string(), depth);
     }
+}
+
+/// Indexes every elf's sections by (line, elf on that line)
+fn build_index(input: &[Assignments]) -> IntervalIndex<(usize, usize)> {
+    let entries = input.iter().enumerate()
+        .flat_map(|(idx, group)| group.iter().enumerate().map(move |(elf, sections)| (*sections, (idx + 1, elf + 1))))
+        .collect();
 
-    overlaps
+    IntervalIndex::new(entries)
 }
 
-fn part1(input: Vec<Assignments>) -> u32 {
-    let mut contains = 0;
+/// Answers a query of either a single section, `7`, or a range, `3-9`
+fn answer_query(index: &IntervalIndex<(usize, usize)>, query: &str) {
+    let range = match query.trim().split_once('-') {
+        Some((start, end)) => start.trim().parse().ok().zip(end.trim().parse().ok()),
+        None => query.trim().parse().ok().map(|section| (section, section)),
+    };
+    let range = match range {
+        Some((start, end)) if start <= end => Interval::new(start, end),
+        _ => {
+            println!("Can't read '{}' as a section or range of sections", query.trim());
+            return;
+        },
+    };
 
-    for assignment in input.into_iter() {
-        if (assignment.0.start <= assignment.1.start && assignment.0.end >= assignment.1.end)
-            || (assignment.0.start >= assignment.1.start && assignment.0.end <= assignment.1.end) {
-            contains += 1;
+    let (found, verb) = if range.start == range.end {
+        (index.covering(range.start), "cover section")
+    } else {
+        (index.overlapping(&range), "overlap")
+    };
+    for (sections, (line, elf)) in found.iter() {
+        println!("  line {}, elf {}: {}", line, elf, sections);
+    }
+    println!("{} of {} assignments {} {}", found.len(), index.len(), verb, query.trim());
+}
+
+fn query(input: &[Assignments], queries: &[String]) {
+    let index = build_index(input);
+
+    if !queries.is_empty() {
+        for query in queries.iter() {
+            answer_query(&index, query);
         }
+        return;
+    }
+
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap();
+        if line.trim().is_empty() { continue }
+        answer_query(&index, &line);
+    }
+}
+
+fn print_groups(input: &[Assignments]) {
+    let mut contained_groups = 0;
+    let mut overlapping_groups = 0;
+
+    for (idx, group) in input.iter().enumerate() {
+        let report = get_group_report(group);
+        let contained: Vec<String> = report.contained.iter().map(|elf| (elf + 1).to_string()).collect();
+        let covered: u64 = report.coverage.iter().map(|i| i.len()).sum();
+
+        println!(
+            "line {}: {} elves, contained: {}, overlap: {}, coverage: {} ({} sections)",
+            idx + 1,
+            group.len(),
+            if contained.is_empty() { "none".to_string() } else { contained.join(", ") },
+            if report.overlapping { "yes" } else { "no" },
+            format_intervals(&report.coverage),
+            covered,
+        );
+
+        if !report.contained.is_empty() { contained_groups += 1 }
+        if report.overlapping { overlapping_groups += 1 }
+    }
+
+    println!("Groups with a contained elf: {}", contained_groups);
+    println!("Groups with any overlap: {}", overlapping_groups);
+}
+
+/// Prints the input with every overlapping group rewritten so nobody shares a section
+fn print_fixed_input(input: &[Assignments]) {
+    let mut changed_sections = 0;
+    let mut changed_groups = 0;
+
+    for (idx, group) in input.iter().enumerate() {
+        let fixed = if has_overlap(group) { reassign::remove_overlaps(group) } else { None };
+        let group = match fixed {
+            Some(reassignment) => {
+                if !reassignment.optimal {
+                    eprintln!("line {}: too many elves to try every order, might not be the fewest changes", idx + 1);
+                }
+                changed_sections += reassignment.changed;
+                changed_groups += 1;
+                reassignment.sections
+            },
+            None => {
+                if has_overlap(group) {
+                    eprintln!("line {}: more elves than sections, left as is", idx + 1);
+                }
+                group.clone()
+            },
+        };
+
+        println!("{}", group.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(","));
     }
 
-    contains
+    eprintln!("Reassigned {} sections across {} groups", changed_sections, changed_groups);
 }
 
 fn main() {
-    let input = get_input();
+    let args: Vec<String> = std::env::args().collect();
+    let input = get_input(args.iter().any(|a| a == "--normalise"));
+
+    if let Some(position) = args.iter().position(|a| a == "query") {
+        let queries: Vec<String> = args[position + 1..].iter().filter(|a| !a.starts_with("--")).cloned().collect();
+        query(&input, &queries);
+        return;
+    }
+    if args.iter().any(|a| a == "fix") {
+        print_fixed_input(&input);
+        return;
+    }
+    if args.iter().any(|a| a == "groups") {
+        print_groups(&input);
+        return;
+    }
+    if args.iter().any(|a| a == "coverage") {
+        print_coverage(&input);
+        return;
+    }
+
+    println!("Part1: {}", part1(&input));
+    println!("Part2: {}", part2(&input));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_group_report() {
+        let group = vec![Interval::new(2, 8), Interval::new(3, 7), Interval::new(12, 15), Interval::new(9, 9)];
+
+        assert_eq!(get_group_report(&group), GroupReport {
+            contained: vec![1],
+            overlapping: true,
+            coverage: vec![Interval::new(2, 9), Interval::new(12, 15)],
+        });
+        assert!(!has_overlap(&[Interval::new(5, 6), Interval::new(1, 2), Interval::new(3, 4)]));
+        assert!(has_overlap(&[Interval::new(1, 10), Interval::new(2, 3), Interval::new(5, 6)]));
 
-    println!("Part1: {}", part1(input.clone()));
-    println!("Part2: {}", part2(input.clone()));
+        // A lone elf is a group too
+        assert_eq!(parse_input("3-5", false), Ok(vec![vec![Interval::new(3, 5)]]));
+        assert_eq!(get_group_report(&[Interval::new(3, 5)]), GroupReport {
+            contained: vec![],
+            overlapping: false,
+            coverage: vec![Interval::new(3, 5)],
+        });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = "2-4,6-8\n8-3,1-2\n1-x,3\n1-2-3\n\n4-5,6-7,";
+
+        assert_eq!(parse_input(input, false), Err(vec![
+            InputError::ReversedRange(2, "8-3".to_string()),
+            InputError::InvalidBound(3, "x".to_string()),
+            InputError::MissingDash(3, "3".to_string()),
+            InputError::BoundCount(4, "1-2-3".to_string()),
+            InputError::EmptyField(6, 3),
+        ]));
+        assert_eq!(parse_input("8-3,1-2", true), Ok(vec![vec![Interval::new(3, 8), Interval::new(1, 2)]]));
+    }
 }
@@ -0,0 +1,188 @@
+use std::collections::{HashMap, hash_map::Entry};
+use day4::interval::{self, Interval};
+
+/// Above this many elves sharing one stretch of sections only the order they start in is tried,
+/// instead of every order, which can cost more than needed
+const MAX_EXACT_ELVES: usize = 6;
+
+/// Which elves have been placed and the index of the cut the last one ends at. With few enough
+/// elves to try every order the placed ones are a bit mask, otherwise they're placed in order and
+/// it's just how many.
+type State = (usize, usize);
+
+/// How many sections change hands when an elf assigned `original` gets `start..end` instead.
+/// `end` is exclusive.
+fn get_cost(original: &Interval, start: u64, end: u64) -> u64 {
+    let overlap_start = start.max(original.start as u64);
+    let overlap_end = end.min(original.end as u64 + 1);
+    let overlap = overlap_end.saturating_sub(overlap_start);
+
+    original.len() + (end - start) - 2 * overlap
+}
+
+/// Splits `segment` between the elves so that each gets one non-empty range and together they
+/// cover it exactly, changing as few section assignments as possible. With more than
+/// `MAX_EXACT_ELVES` elves they keep the order they start in, so the split might not be the cheapest.
+fn tile_segment(segment: &Interval, elves: &[(usize, Interval)]) -> Option<(u64, Vec<(usize, Interval)>)> {
+    if segment.len() < elves.len() as u64 {
+        return None;
+    }
+
+    // Cuts only ever need to sit on, or a few sections away from, the edge of an assignment
+    let segment_end = segment.end as u64 + 1;
+    let mut cuts: Vec<u64> = Vec::new();
+    for edge in elves.iter().flat_map(|(_, i)| [i.start as u64, i.end as u64 + 1]).chain([segment.start as u64, segment_end]) {
+        for offset in 0..=elves.len() as u64 {
+            cuts.push(edge.saturating_sub(offset).max(segment.start as u64));
+            cuts.push((edge + offset).min(segment_end));
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    let exact = elves.len() <= MAX_EXACT_ELVES;
+    let full = if exact { (1usize << elves.len()) - 1 } else { elves.len() };
+
+    // Cheapest way to reach each state, with the state before it and the elf placed in between
+    let mut best: HashMap<State, (u64, State, usize)> = HashMap::new();
+    let mut states: Vec<State> = vec![(0, 0)];
+    best.insert((0, 0), (0, (0, 0), 0));
+
+    for placed in 0..elves.len() {
+        let mut next_states: Vec<State> = Vec::new();
+        for (mask, cut) in states.into_iter() {
+            let cost = best[&(mask, cut)].0;
+            let candidates: Vec<usize> = if exact {
+                (0..elves.len()).filter(|e| mask & 1 << e == 0).collect()
+            } else {
+                vec![placed]
+            };
+
+            for elf in candidates {
+                let next_mask = if exact { mask | 1 << elf } else { placed + 1 };
+                let next_cuts = if next_mask == full { cuts.len() - 1..cuts.len() } else { cut + 1..cuts.len() - 1 };
+                for next_cut in next_cuts {
+                    if next_cut <= cut { continue }
+                    let next_cost = cost + get_cost(&elves[elf].1, cuts[cut], cuts[next_cut]);
+                    match best.entry((next_mask, next_cut)) {
+                        Entry::Occupied(mut occupied) => {
+                            if next_cost < occupied.get().0 {
+                                occupied.insert((next_cost, (mask, cut), elf));
+                            }
+                        },
+                        Entry::Vacant(vacant) => {
+                            vacant.insert((next_cost, (mask, cut), elf));
+                            next_states.push((next_mask, next_cut));
+                        },
+                    }
+                }
+            }
+        }
+        states = next_states;
+    }
+
+    let mut state = (full, cuts.len() - 1);
+    let total_cost = best.get(&state)?.0;
+    let mut tiles = Vec::new();
+    while state.0 != 0 {
+        let (_, previous, elf) = best[&state];
+        tiles.push((elves[elf].0, Interval::new(cuts[previous.1] as u32, (cuts[state.1] - 1) as u32)));
+        state = previous;
+    }
+
+    Some((total_cost, tiles))
+}
+
+/// A group with its overlaps removed
+#[derive(Debug, PartialEq)]
+pub struct Reassignment {
+    /// The new assignments, in the same order as the group
+    pub sections: Vec<Interval>,
+    /// How many sections changed hands
+    pub changed: u64,
+    /// Whether `changed` is known to be as low as it can go
+    pub optimal: bool,
+}
+
+/// Reassigns the group so no two elves share a section while the sections covered as a whole stay
+/// the same, or returns `None` if there are more elves than sections to go around. As long as no
+/// more than `MAX_EXACT_ELVES` elves share a stretch of sections, as few section assignments as
+/// possible are changed. Past that it's a heuristic that keeps the elves in the order they start
+/// in, and the result is marked as not optimal.
+pub fn remove_overlaps(group: &[Interval]) -> Option<Reassignment> {
+    let mut reassigned = group.to_vec();
+    let mut total_cost = 0;
+    let mut optimal = true;
+
+    // Every assignment lies within one stretch of the combined coverage, each stretch is split
+    // between the elves already in it
+    for segment in interval::merge(group).iter() {
+        let mut elves: Vec<(usize, Interval)> = group.iter().copied().enumerate()
+            .filter(|(_, sections)| segment.contains(sections))
+            .collect();
+        elves.sort_by_key(|(_, sections)| *sections);
+        let (cost, tiles) = tile_segment(segment, &elves)?;
+
+        total_cost += cost;
+        optimal &= elves.len() <= MAX_EXACT_ELVES;
+        for (elf, sections) in tiles {
+            reassigned[elf] = sections;
+        }
+    }
+
+    Some(Reassignment { sections: reassigned, changed: total_cost, optimal })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_remove_overlaps() {
+        let Reassignment { sections: fixed, changed: cost, optimal } = remove_overlaps(&[Interval::new(5, 7), Interval::new(7, 9)]).unwrap();
+        assert_eq!(cost, 1);
+        assert!(optimal);
+        assert!(!fixed[0].overlaps(&fixed[1]));
+        assert_eq!(interval::merge(&fixed), vec![Interval::new(5, 9)]);
+
+        let Reassignment { sections: fixed, changed: cost, .. } = remove_overlaps(&[Interval::new(1, 10), Interval::new(2, 3)]).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(interval::merge(&fixed), vec![Interval::new(1, 10)]);
+
+        let Reassignment { sections: fixed, changed: cost, .. } = remove_overlaps(&[Interval::new(2, 4), Interval::new(6, 8)]).unwrap();
+        assert_eq!(cost, 0);
+        assert_eq!(fixed, vec![Interval::new(2, 4), Interval::new(6, 8)]);
+
+        assert_eq!(remove_overlaps(&[Interval::new(5, 5), Interval::new(5, 5)]), None);
+
+        // Keeping the order they start in would change 19
+        let group = [Interval::new(21, 24), Interval::new(13, 24), Interval::new(27, 28), Interval::new(13, 27)];
+        let reassignment = remove_overlaps(&group).unwrap();
+        assert_eq!(reassignment.changed, 17);
+        assert!(reassignment.optimal);
+    }
+
+    #[test]
+    fn test_remove_overlaps_in_large_group() {
+        let group: Vec<Interval> = (0..10).map(|i| Interval::new(i, i + 5)).collect();
+        let reassignment = remove_overlaps(&group).unwrap();
+        assert!(!reassignment.optimal);
+
+        let fixed = reassignment.sections;
+        assert_eq!(interval::merge(&fixed), vec![Interval::new(0, 14)]);
+        let mut sorted = fixed.clone();
+        sorted.sort();
+        assert!(sorted.windows(2).all(|w| !w[0].overlaps(&w[1])));
+    }
+
+    #[test]
+    fn test_remove_overlaps_with_more_elves_than_mask_bits() {
+        let reassignment = remove_overlaps(&vec![Interval::new(1, 200); 64]).unwrap();
+        assert!(!reassignment.optimal);
+        let fixed = reassignment.sections;
+        assert_eq!(interval::merge(&fixed), vec![Interval::new(1, 200)]);
+        assert_eq!(fixed.iter().map(|i| i.len()).sum::<u64>(), 200);
+
+        assert_eq!(remove_overlaps(&vec![Interval::new(1, 200); 201]), None);
+    }
+}
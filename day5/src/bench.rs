@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+use day5::{build_stacks, apply_moves, crane::{Crane, CrateMover9000, CrateMover9001}, Move, Stack};
+
+/// The move loops parts 1 and 2 started out with, which `pop` and `stack` one crate at a time.
+/// The bulk cranes are checked and timed against these.
+pub struct OneAtATime {
+    pub keep_order: bool,
+}
+
+impl Crane for OneAtATime {
+    fn name(&self) -> String {
+        format!("One at a time ({})", if self.keep_order { "9001" } else { "9000" })
+    }
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move) {
+        if !self.keep_order {
+            for _ in 0..crane_move.amount {
+                let handling_crate = stacks[crane_move.from - 1].pop();
+                stacks[crane_move.to - 1].stack(handling_crate);
+            }
+            return;
+        }
+
+        let mut handling_crates = Vec::new();
+        for _ in 0..crane_move.amount {
+            handling_crates.push(stacks[crane_move.from - 1].pop());
+        }
+        for handling_crate in handling_crates.into_iter().rev() {
+            stacks[crane_move.to - 1].stack(handling_crate);
+        }
+    }
+}
+
+/// A small xorshift generator, so the same input comes out every run without pulling in a crate
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, below: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % below as u64) as usize
+    }
+}
+
+/// Builds `stack_count` stacks of `height` crates and `move_count` valid moves of up to
+/// `max_amount` crates each. Some moves put the crates back on the stack they came from.
+pub fn generate(stack_count: usize, height: usize, move_count: usize, max_amount: usize) -> (Vec<Stack>, Vec<Move>) {
+    let mut random = Random(0x2022_0005);
+    let stacks = build_stacks((0..stack_count).map(|_| {
+        (0..height).map(|_| ((b'A' + random.next(26) as u8) as char).to_string()).collect()
+    }).collect());
+
+    let mut heights = vec![height; stack_count];
+    let mut moves = Vec::with_capacity(move_count);
+    while moves.len() < move_count {
+        let from = random.next(stack_count);
+        let to = random.next(stack_count);
+        if heights[from] == 0 { continue }
+
+        let amount = 1 + random.next(heights[from].min(max_amount));
+        heights[from] -= amount;
+        heights[to] += amount;
+        moves.push(Move { amount: amount as u32, from: from + 1, to: to + 1, line: moves.len() + 1 });
+    }
+
+    (stacks, moves)
+}
+
+fn time(crane: &dyn Crane, stacks: &[Stack], moves: &[Move]) -> (Duration, Vec<Stack>) {
+    let mut stacks = stacks.to_vec();
+    let start = Instant::now();
+    apply_moves(crane, &mut stacks, moves);
+
+    (start.elapsed(), stacks)
+}
+
+/// Times the bulk transfer cranes against the one-at-a-time baseline on a generated input
+pub fn run_benchmark(stack_count: usize, height: usize, move_count: usize, max_amount: usize) {
+    if stack_count == 0 || max_amount == 0 || (height == 0 && move_count > 0) {
+        eprintln!("The benchmark needs at least one stack, crates to move and a --max-amount of at least 1");
+        return;
+    }
+    println!("Generating {} stacks of {} crates and {} moves...", stack_count, height, move_count);
+    let (stacks, moves) = generate(stack_count, height, move_count, max_amount);
+
+    let pairs: [(&dyn Crane, &dyn Crane); 2] = [
+        (&OneAtATime { keep_order: false }, &CrateMover9000),
+        (&OneAtATime { keep_order: true }, &CrateMover9001),
+    ];
+    for (baseline, bulk) in pairs.into_iter() {
+        let (baseline_time, baseline_result) = time(baseline, &stacks, &moves);
+        let (bulk_time, bulk_result) = time(bulk, &stacks, &moves);
+        assert!(baseline_result == bulk_result, "{} and {} disagree", baseline.name(), bulk.name());
+
+        println!("{:<22} {:>10.2?}", baseline.name(), baseline_time);
+        println!("{:<22} {:>10.2?} ({:.1}x)", bulk.name(), bulk_time, baseline_time.as_secs_f64() / bulk_time.as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day5::{crane::{AlternatingCrane, CapacityCrane}, run};
+
+    #[test]
+    fn test_bulk_matches_one_at_a_time() {
+        let (stacks, moves) = generate(5, 30, 300, 20);
+
+        assert_eq!(run(&CrateMover9000, &stacks, &moves), run(&OneAtATime { keep_order: false }, &stacks, &moves));
+        assert_eq!(run(&CrateMover9001, &stacks, &moves), run(&OneAtATime { keep_order: true }, &stacks, &moves));
+        assert_eq!(run(&CapacityCrane { capacity: 1 }, &stacks, &moves), run(&CrateMover9000, &stacks, &moves));
+        assert_eq!(run(&CapacityCrane { capacity: 20 }, &stacks, &moves), run(&CrateMover9001, &stacks, &moves));
+        // Reversing every other crate twice puts them back
+        let twice: Vec<Move> = moves.iter().flat_map(|m| [m.clone(), Move { from: m.to, to: m.to, ..m.clone() }]).collect();
+        assert_eq!(run(&AlternatingCrane, &stacks, &twice), run(&CrateMover9001, &stacks, &moves));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Move, Stack};
+
+/// A model of crane, which decides what order the crates of a move end up in
+pub trait Crane {
+    fn name(&self) -> String;
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move);
+}
+
+/// Moves the top crates of `from` onto `to` in one go, reversing them unless `keep_order` is set
+fn transfer(stacks: &mut [Stack], crane_move: &Move, keep_order: bool) {
+    let (from, to) = (crane_move.from - 1, crane_move.to - 1);
+    let amount = crane_move.amount as usize;
+
+    // Crates put back on the stack they came from end up where they were, one at a time or not
+    if from == to {
+        return;
+    }
+
+    let (source, destination) = if from < to {
+        let (left, right) = stacks.split_at_mut(to);
+        (&mut left[from], &mut right[0])
+    } else {
+        let (left, right) = stacks.split_at_mut(from);
+        (&mut right[0], &mut left[to])
+    };
+    let split = source.crates.len() - amount;
+    if keep_order {
+        destination.crates.extend(source.crates.drain(split..));
+    } else {
+        destination.crates.extend(source.crates.drain(split..).rev());
+    }
+}
+
+/// Moves one crate at a time, so a move reverses the crates
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move) {
+        transfer(stacks, crane_move, false);
+    }
+}
+
+/// Moves all crates at once, so they keep their order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move) {
+        transfer(stacks, crane_move, true);
+    }
+}
+
+/// Like the 9001, but lifts at most `capacity` crates at a time. Bigger moves are done in chunks
+/// from the top down, each chunk keeping its order.
+pub struct CapacityCrane {
+    pub capacity: u32,
+}
+
+impl Crane for CapacityCrane {
+    fn name(&self) -> String {
+        format!("Capacity crane ({})", self.capacity)
+    }
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move) {
+        let mut remaining = crane_move.amount;
+        while remaining > 0 {
+            let chunk = Move {
+                amount: remaining.min(self.capacity),
+                ..crane_move.clone()
+            };
+            CrateMover9001.apply(stacks, &chunk);
+            remaining -= chunk.amount;
+        }
+    }
+}
+
+/// Like the 9001, but the first, third, fifth... crate of each move (counting from the top) come
+/// down in reverse order among themselves, while the crates in between keep their place
+pub struct AlternatingCrane;
+
+impl Crane for AlternatingCrane {
+    fn name(&self) -> String {
+        "Alternating crane".to_string()
+    }
+
+    fn apply(&self, stacks: &mut [Stack], crane_move: &Move) {
+        transfer(stacks, crane_move, true);
+
+        let destination = &mut stacks[crane_move.to - 1].crates;
+        let amount = crane_move.amount as usize;
+        let moved = destination.len() - amount..destination.len();
+        // Positions of the first, third, fifth... moved crate counting from the top
+        let every_other: Vec<usize> = moved.rev().step_by(2).collect();
+        for idx in 0..every_other.len() / 2 {
+            destination.swap(every_other[idx], every_other[every_other.len() - 1 - idx]);
+        }
+    }
+}
+
+/// Picks a crane by name: `9000`, `9001`, `alternating` or `capacity:<n>`
+pub fn get_crane(name: &str) -> Option<Box<dyn Crane>> {
+    match name {
+        "9000" => Some(Box::new(CrateMover9000)),
+        "9001" => Some(Box::new(CrateMover9001)),
+        "alternating" => Some(Box::new(AlternatingCrane)),
+        _ => {
+            let capacity: u32 = name.strip_prefix("capacity:")?.parse().ok()?;
+            if capacity == 0 {
+                return None;
+            }
+            Some(Box::new(CapacityCrane { capacity }))
+        },
+    }
+}
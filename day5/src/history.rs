@@ -0,0 +1,182 @@
+use crate::{crane::Crane, Move, Stack};
+
+/// The moves made so far on a set of stacks, with undo and redo. A copy of the stacks is kept
+/// every `interval` moves, so getting the state at any step replays at most `interval` moves
+/// instead of everything from the start.
+pub struct History<'a> {
+    crane: &'a dyn Crane,
+    moves: Vec<Move>,
+    position: usize,
+    current: Vec<Stack>,
+    /// `checkpoints[n]` is the state after the first `n * interval` moves
+    checkpoints: Vec<Vec<Stack>>,
+    interval: usize,
+}
+
+impl<'a> History<'a> {
+    pub fn new(crane: &'a dyn Crane, initial: Vec<Stack>, interval: usize) -> Self {
+        History {
+            crane,
+            moves: Vec::new(),
+            position: 0,
+            current: initial.clone(),
+            checkpoints: vec![initial],
+            interval: interval.max(1),
+        }
+    }
+
+    /// Starts at the initial state with `moves` ready to be redone. The moves are run through once
+    /// up front to take the checkpoints, so `state_at` is quick straight away.
+    pub fn with_moves(crane: &'a dyn Crane, initial: Vec<Stack>, moves: Vec<Move>, interval: usize) -> Self {
+        let mut history = History {
+            moves,
+            ..Self::new(crane, initial, interval)
+        };
+        history.seek(history.moves.len());
+        history.seek(0);
+
+        history
+    }
+
+    /// The stacks after the moves up to the current position
+    pub fn current(&self) -> &[Stack] {
+        &self.current
+    }
+
+    /// How many moves have been applied
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// How many moves are recorded, including the ones that were undone
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    fn step_forward(&mut self) {
+        self.crane.apply(&mut self.current, &self.moves[self.position]);
+        self.position += 1;
+
+        if self.position.is_multiple_of(self.interval) && self.checkpoints.len() == self.position / self.interval {
+            self.checkpoints.push(self.current.clone());
+        }
+    }
+
+    /// Applies a new move. Anything that was undone can't be redone anymore.
+    pub fn apply(&mut self, crane_move: Move) {
+        self.moves.truncate(self.position);
+        self.checkpoints.truncate(self.position / self.interval + 1);
+        self.moves.push(crane_move);
+        self.step_forward();
+    }
+
+    /// Takes back the last move, returning it
+    pub fn undo(&mut self) -> Option<&Move> {
+        if self.position == 0 {
+            return None;
+        }
+        self.seek(self.position - 1);
+
+        self.moves.get(self.position)
+    }
+
+    /// Applies the move that was last undone again, returning it
+    pub fn redo(&mut self) -> Option<&Move> {
+        if self.position == self.moves.len() {
+            return None;
+        }
+        self.step_forward();
+
+        self.moves.get(self.position - 1)
+    }
+
+    /// Goes to the state after `step` moves, as far as there are moves recorded
+    pub fn seek(&mut self, step: usize) {
+        let step = step.min(self.moves.len());
+        if step < self.position || step - self.position > self.interval {
+            let checkpoint = (step / self.interval).min(self.checkpoints.len() - 1);
+            self.current = self.checkpoints[checkpoint].clone();
+            self.position = checkpoint * self.interval;
+        }
+
+        while self.position < step {
+            self.step_forward();
+        }
+    }
+
+    /// The stacks after the first `step` recorded moves, without moving the current position
+    pub fn state_at(&self, step: usize) -> Option<Vec<Stack>> {
+        if step > self.moves.len() {
+            return None;
+        }
+
+        let checkpoint = (step / self.interval).min(self.checkpoints.len() - 1);
+        let mut stacks = self.checkpoints[checkpoint].clone();
+        for crane_move in self.moves[checkpoint * self.interval..step].iter() {
+            self.crane.apply(&mut stacks, crane_move);
+        }
+
+        Some(stacks)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{crane::CrateMover9000, parse_input, run};
+    use std::fs::read_to_string;
+
+    #[test]
+    fn test_history_matches_replaying() {
+        let (stacks, moves) = parse_input(&read_to_string("input.txt").unwrap()).unwrap();
+        let mut history = History::with_moves(&CrateMover9000, stacks.clone(), moves.clone(), 50);
+        assert_eq!(history.position(), 0);
+        assert_eq!(history.checkpoints.len(), moves.len() / 50 + 1);
+
+        for step in [0, 1, 49, 50, 51, 333, moves.len()] {
+            assert_eq!(history.state_at(step).unwrap(), run(&CrateMover9000, &stacks, &moves[..step]));
+        }
+        assert_eq!(history.position(), 0);
+
+        for step in [moves.len(), 0, 1, 49, 50, 51, 333] {
+            let expected = run(&CrateMover9000, &stacks, &moves[..step]);
+            history.seek(step);
+            assert_eq!(history.current(), expected.as_slice());
+        }
+        assert!(history.state_at(moves.len() + 1).is_none());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (stacks, moves) = parse_input(&read_to_string("input.txt").unwrap()).unwrap();
+        let mut history = History::new(&CrateMover9000, stacks.clone(), 3);
+        for crane_move in moves[..10].iter() {
+            history.apply(crane_move.clone());
+        }
+
+        assert_eq!(history.undo().map(|m| m.line), Some(moves[9].line));
+        assert_eq!(history.undo().map(|m| m.line), Some(moves[8].line));
+        assert_eq!(history.current(), run(&CrateMover9000, &stacks, &moves[..8]).as_slice());
+        assert_eq!(history.redo().map(|m| m.line), Some(moves[8].line));
+
+        // A new move drops whatever was undone
+        history.apply(moves[20].clone());
+        assert_eq!(history.len(), 10);
+        assert!(history.redo().is_none());
+        let mut expected_moves = moves[..9].to_vec();
+        expected_moves.push(moves[20].clone());
+        assert_eq!(history.current(), run(&CrateMover9000, &stacks, &expected_moves).as_slice());
+
+        history.seek(0);
+        assert!(history.undo().is_none());
+        assert_eq!(history.current(), stacks.as_slice());
+    }
+}
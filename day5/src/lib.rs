@@ -0,0 +1,368 @@
+//! Parsing, drawing and simulating the day 5 crate stacks, so tools other than the puzzle binary
+//! can step through a simulation
+
+pub mod crane;
+pub mod history;
+
+use std::{fmt, rc::Rc};
+use regex::Regex;
+use crane::Crane;
+
+/// Where a crate was in the drawing: its stack, counting from 1, and how many crates were on top
+/// of it. No two crates share an origin, so it doubles as the crate's identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Origin {
+    pub stack: usize,
+    pub depth: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "stack {} depth {}", self.stack, self.depth)
+    }
+}
+
+/// A crate in the stacks. The label is shared, so copying the stacks to run another simulation
+/// doesn't copy every label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crate {
+    pub label: Rc<str>,
+    pub origin: Origin,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stack {
+    pub crates: Vec<Crate>,
+}
+
+impl Stack {
+    pub fn stack(&mut self, new_crate: Crate) {
+        self.crates.push(new_crate);
+    }
+
+    pub fn pop(&mut self) -> Crate {
+        self.crates.pop().unwrap()
+    }
+
+    pub fn read(&self) -> &str {
+        match self.crates.last() {
+            Some(top) => &top.label,
+            None => " ",
+        }
+    }
+
+    pub fn labels(&self) -> Vec<&str> {
+        self.crates.iter().map(|c| &*c.label).collect()
+    }
+}
+
+/// Builds stacks from their crate labels, bottom crate first, giving every crate its origin
+pub fn build_stacks(labels: Vec<Vec<String>>) -> Vec<Stack> {
+    labels.into_iter().enumerate().map(|(idx, labels)| {
+        let height = labels.len();
+        Stack {
+            crates: labels.into_iter().enumerate().map(|(level, label)| Crate {
+                label: label.into(),
+                origin: Origin { stack: idx + 1, depth: height - 1 - level },
+            }).collect(),
+        }
+    }).collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct Move {
+    pub amount: u32,
+    pub from: usize,
+    pub to: usize,
+    /// Where in the input the move came from, starting at 1
+    pub line: usize,
+}
+
+/// A move that can't be carried out, with the state of the stacks at that point
+#[derive(Debug, PartialEq)]
+pub enum MoveError {
+    NoSuchStack { line: usize, stack: usize, stacks: usize },
+    NotEnoughCrates { line: usize, stack: usize, height: usize, amount: u32 },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack { line, stack, stacks } => write!(f, "line {}: there is no stack {}, stacks are numbered 1 to {}", line, stack, stacks),
+            MoveError::NotEnoughCrates { line, stack, height, amount } => write!(f, "line {}: can't move {} crates from stack {}, it only holds {} at that point", line, amount, stack, height),
+        }
+    }
+}
+
+/// A problem with the puzzle input. Line and column numbers start at 1.
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    MissingStackNumbers,
+    StackNumber(usize, String),
+    UnclosedCrate(usize, usize),
+    UnexpectedText(usize, usize, char),
+    MisalignedCrate(usize, usize, String),
+    InvalidMove(usize, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::MissingStackNumbers => write!(f, "the drawing has no line of stack numbers"),
+            ParseError::StackNumber(line, found) => write!(f, "line {}: expected stacks numbered 1, 2, 3..., found '{}'", line, found),
+            ParseError::UnclosedCrate(line, column) => write!(f, "line {}, column {}: crate is missing its closing ']'", line, column),
+            ParseError::UnexpectedText(line, column, found) => write!(f, "line {}, column {}: expected a crate like '[A]', found '{}'", line, column, found),
+            ParseError::MisalignedCrate(line, column, label) => write!(f, "line {}, column {}: crate [{}] doesn't line up with exactly one stack number", line, column, label),
+            ParseError::InvalidMove(line, found) => write!(f, "line {}: expected 'move <n> from <stack> to <stack>', found '{}'", line, found),
+        }
+    }
+}
+
+/// The (start, end) char positions of every run of non-space characters in `line`, end exclusive
+fn get_tokens(line: &[char]) -> Vec<(usize, usize)> {
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < line.len() {
+        if line[idx] == ' ' {
+            idx += 1;
+            continue;
+        }
+        let start = idx;
+        while idx < line.len() && line[idx] != ' ' {
+            idx += 1;
+        }
+        tokens.push((start, idx));
+    }
+
+    tokens
+}
+
+/// Reads the drawing of the stacks. The last line numbers the stacks, and every crate belongs to
+/// the stack whose number it sits above, so stacks and crate labels can be any width.
+pub fn parse_drawing(lines: &[&str]) -> Result<Vec<Stack>, ParseError> {
+    let (footer, rows) = lines.split_last().ok_or(ParseError::MissingStackNumbers)?;
+    let footer_line_number = lines.len();
+
+    let footer_chars: Vec<char> = footer.chars().collect();
+    let columns = get_tokens(&footer_chars);
+    for (idx, (start, end)) in columns.iter().enumerate() {
+        let number: String = footer_chars[*start..*end].iter().collect();
+        if number != (idx + 1).to_string() {
+            return Err(ParseError::StackNumber(footer_line_number, footer.trim().to_string()));
+        }
+    }
+    if columns.is_empty() {
+        return Err(ParseError::MissingStackNumbers);
+    }
+
+    let mut labels: Vec<Vec<String>> = vec![Vec::new(); columns.len()];
+    for (row_idx, row) in rows.iter().enumerate().rev() {
+        let line_number = row_idx + 1;
+        let row_chars: Vec<char> = row.chars().collect();
+
+        let mut idx = 0;
+        while idx < row_chars.len() {
+            if row_chars[idx] == ' ' {
+                idx += 1;
+                continue;
+            }
+            if row_chars[idx] != '[' {
+                return Err(ParseError::UnexpectedText(line_number, idx + 1, row_chars[idx]));
+            }
+
+            let start = idx;
+            let end = match row_chars[start..].iter().position(|c| *c == ']') {
+                Some(offset) => start + offset + 1,
+                None => return Err(ParseError::UnclosedCrate(line_number, start + 1)),
+            };
+            let label: String = row_chars[start + 1..end - 1].iter().collect();
+
+            let under: Vec<usize> = columns.iter().enumerate()
+                .filter(|(_, (column_start, column_end))| *column_start < end && start < *column_end)
+                .map(|(stack, _)| stack)
+                .collect();
+            if under.len() != 1 || label.is_empty() {
+                return Err(ParseError::MisalignedCrate(line_number, start + 1, label));
+            }
+
+            labels[under[0]].push(label);
+            idx = end;
+        }
+    }
+
+    Ok(build_stacks(labels))
+}
+
+/// Reads the moves from `lines`, numbered from 1, in the order they're given. Blank lines are
+/// skipped.
+fn parse_moves<'a>(lines: impl Iterator<Item = (usize, &'a str)> + 'a) -> impl Iterator<Item = Result<Move, ParseError>> + 'a {
+    let move_re = Regex::new("^move ([0-9]+) from ([0-9]+) to ([0-9]+)$").unwrap();
+
+    lines.filter(|(_, line)| !line.trim().is_empty()).map(move |(idx, line)| {
+        let invalid = || ParseError::InvalidMove(idx + 1, line.to_string());
+        let captures = move_re.captures(line.trim()).ok_or_else(invalid)?;
+        // Numbers too big for their field are as invalid as ones that aren't numbers
+        let number = |group: usize| captures.get(group).unwrap().as_str();
+
+        Ok(Move {
+            amount: number(1).parse().map_err(|_| invalid())?,
+            from: number(2).parse().map_err(|_| invalid())?,
+            to: number(3).parse().map_err(|_| invalid())?,
+            line: idx + 1,
+        })
+    })
+}
+
+pub fn parse_input(input: &str) -> Result<(Vec<Stack>, Vec<Move>), ParseError> {
+    let mut lines = input.lines().enumerate().peekable();
+
+    let mut drawing = Vec::new();
+    while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty() && !line.starts_with("move")) {
+        drawing.push(line);
+    }
+    let stacks = parse_drawing(&drawing)?;
+    let moves = parse_moves(lines).collect::<Result<Vec<Move>, ParseError>>()?;
+
+    Ok((stacks, moves))
+}
+
+/// Draws the stacks the way the puzzle input does, so `parse_input(&render(stacks))` gives them
+/// back. Every column is as wide as its widest crate or its number, whichever is wider, and empty
+/// stacks get the usual width of a one letter crate.
+pub fn render(stacks: &[Stack]) -> String {
+    let numbers: Vec<String> = (1..=stacks.len()).map(|n| n.to_string()).collect();
+    let widths: Vec<usize> = stacks.iter().zip(numbers.iter())
+        .map(|(stack, number)| {
+            let widest_crate = stack.labels().iter().map(|l| l.chars().count() + 2).max().unwrap_or(3);
+            widest_crate.max(number.len())
+        })
+        .collect();
+    let height = stacks.iter().map(|s| s.crates.len()).max().unwrap_or(0);
+
+    let mut lines = Vec::new();
+    for level in (0..height).rev() {
+        let cells: Vec<String> = stacks.iter().zip(widths.iter())
+            .map(|(stack, width)| match stack.crates.get(level) {
+                Some(found) => format!("{:<width$}", format!("[{}]", found.label), width = width),
+                None => " ".repeat(*width),
+            })
+            .collect();
+        lines.push(cells.join(" ").trim_end().to_string());
+    }
+
+    let footer: Vec<String> = numbers.iter().zip(widths.iter())
+        .map(|(number, width)| {
+            let left = (width - number.len()) / 2;
+            format!("{}{:<width$}", " ".repeat(left), number, width = width - left)
+        })
+        .collect();
+    lines.push(footer.join(" ").trim_end().to_string());
+
+    lines.join("\n")
+}
+
+pub fn construct_answer(stacks: &[Stack]) -> String {
+    let mut answer = String::new();
+    for stack in stacks.iter() {
+        answer.push_str(stack.read());
+    }
+
+    answer
+}
+
+/// Checks every move against the stacks as they'll be when it's made. Only the heights matter for
+/// that, so this doesn't depend on the crane model.
+pub fn validate_moves(stacks: &[Stack], moves: &[Move]) -> Result<(), MoveError> {
+    let mut heights: Vec<usize> = stacks.iter().map(|s| s.crates.len()).collect();
+
+    for crane_move in moves.iter() {
+        for stack in [crane_move.from, crane_move.to] {
+            if stack == 0 || stack > heights.len() {
+                return Err(MoveError::NoSuchStack { line: crane_move.line, stack, stacks: heights.len() });
+            }
+        }
+
+        let height = heights[crane_move.from - 1];
+        if (crane_move.amount as usize) > height {
+            return Err(MoveError::NotEnoughCrates { line: crane_move.line, stack: crane_move.from, height, amount: crane_move.amount });
+        }
+        heights[crane_move.from - 1] -= crane_move.amount as usize;
+        heights[crane_move.to - 1] += crane_move.amount as usize;
+    }
+
+    Ok(())
+}
+
+/// Applies every move in place, they must have passed `validate_moves` against these stacks
+pub fn apply_moves(crane: &dyn Crane, stacks: &mut [Stack], moves: &[Move]) {
+    for crane_move in moves.iter() {
+        crane.apply(stacks, crane_move);
+    }
+}
+
+/// Applies every move to a copy of `initial`, leaving it as it is for the next simulation
+pub fn run(crane: &dyn Crane, initial: &[Stack], moves: &[Move]) -> Vec<Stack> {
+    let mut stacks = initial.to_vec();
+    apply_moves(crane, &mut stacks, moves);
+
+    stacks
+}
+
+pub fn simulate(crane: &dyn Crane, initial: &[Stack], moves: &[Move]) -> String {
+    construct_answer(&run(crane, initial, moves))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use std::fs::read_to_string;
+
+    fn get_input() -> (Vec<Stack>, Vec<Move>) {
+        parse_input(&read_to_string("input.txt").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_input("  [A]\n 1   2").unwrap_err(), ParseError::MisalignedCrate(1, 3, "A".to_string()));
+        assert_eq!(parse_input("[A] x\n 1   2").unwrap_err(), ParseError::UnexpectedText(1, 5, 'x'));
+        assert_eq!(parse_input("[A\n 1").unwrap_err(), ParseError::UnclosedCrate(1, 1));
+        assert_eq!(parse_input("[A]\n 1   3").unwrap_err(), ParseError::StackNumber(2, "1   3".to_string()));
+        assert_eq!(parse_input("[A]\n 1\n\nmove 1 from 1").unwrap_err(), ParseError::InvalidMove(4, "move 1 from 1".to_string()));
+        assert_eq!(parse_input("[A]\n 1   2\n\nmove 4294967297 from 1 to 2").unwrap_err(), ParseError::InvalidMove(4, "move 4294967297 from 1 to 2".to_string()));
+    }
+
+    #[test]
+    fn test_render_matches_input() {
+        let file_contents = read_to_string("input.txt").unwrap();
+        let drawing: Vec<&str> = file_contents.lines().take_while(|l| !l.is_empty()).map(|l| l.trim_end()).collect();
+
+        assert_eq!(render(&get_input().0), drawing.join("\n"));
+    }
+
+    #[test]
+    fn test_validate_moves() {
+        let (stacks, moves) = parse_input("[A]\n[B] [C]\n 1   2\n\nmove 2 from 1 to 2\nmove 4 from 2 to 1").unwrap();
+        assert_eq!(validate_moves(&stacks, &moves), Err(MoveError::NotEnoughCrates { line: 6, stack: 2, height: 3, amount: 4 }));
+
+        let (stacks, moves) = parse_input("[A]\n 1\n\nmove 1 from 1 to 0").unwrap();
+        assert_eq!(validate_moves(&stacks, &moves), Err(MoveError::NoSuchStack { line: 4, stack: 0, stacks: 1 }));
+
+        let input = get_input();
+        assert_eq!(validate_moves(&input.0, &input.1), Ok(()));
+    }
+
+    fn stacks() -> impl Strategy<Value = Vec<Stack>> {
+        prop::collection::vec(prop::collection::vec("[A-Za-z0-9]{1,3}", 0..6), 1..14)
+            .prop_map(build_stacks)
+    }
+
+    proptest! {
+        #[test]
+        fn render_round_trips(stacks in stacks()) {
+            let (parsed, moves) = parse_input(&render(&stacks)).unwrap();
+            prop_assert_eq!(parsed, stacks);
+            prop_assert!(moves.is_empty());
+        }
+    }
+}
@@ -1,145 +1,221 @@
-use std::{fs::read_to_string};
-use regex::Regex;
+mod bench;
+mod planner;
+mod provenance;
+mod replay;
 
-#[derive(Debug, Clone)]
-struct Stack {
-    crates: Vec<char>,
-}
+use std::{fs::read_to_string, process, time::Duration};
+use day5::{construct_answer, crane::{self, Crane, CrateMover9000, CrateMover9001}, parse_drawing, parse_input, render, run, simulate, validate_moves, Move, Stack};
 
-impl Stack {
-    pub fn new() -> Self {
-        Stack {
-            crates: Vec::new(),
-        }
-    }
+fn part1(stacks: &[Stack], moves: &[Move]) -> String {
+    simulate(&CrateMover9000, stacks, moves)
+}
 
-    pub fn stack(&mut self, new_crate: char) {
-        self.crates.push(new_crate);
-    }
+fn part2(stacks: &[Stack], moves: &[Move]) -> String {
+    simulate(&CrateMover9001, stacks, moves)
+}
 
-    pub fn pop(&mut self) -> char {
-        self.crates.pop().unwrap()
-    }
+/// Every value given for `flag`, as in `--flag value`
+fn get_flag_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.iter().skip(1).zip(args.iter().skip(2))
+        .filter(|(name, _)| *name == flag)
+        .map(|(_, value)| value.as_str())
+        .collect()
+}
 
-    pub fn read(&self) -> &char {
-        if self.crates.is_empty() {
-            return &' ';
-        }
-        &self.crates[self.crates.len() - 1]
+fn get_flag_number(args: &[String], flag: &str, default: u64) -> u64 {
+    match get_flag_values(args, flag).first() {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            eprintln!("Expected a number for {}, found '{}'", flag, value);
+            process::exit(1);
+        }),
+        None => default,
     }
 }
 
-#[derive(Debug, Clone)]
-struct Move {
-    amount: u32,
-    from: usize,
-    to: usize,
+/// Looks up every crane named with `--crane`, exiting on the first one that doesn't exist
+fn get_cranes(names: &[&str]) -> Vec<Box<dyn Crane>> {
+    names.iter().map(|name| crane::get_crane(name).unwrap_or_else(|| {
+        eprintln!("Unknown crane '{}', expected 9000, 9001, alternating or capacity:<n>", name);
+        process::exit(1);
+    })).collect()
 }
 
-fn parse_input(input: &str) -> (Vec<Stack>, Vec<Move>) {
-    let mut stacks = Vec::new();
-    let mut moves = Vec::new();
-
-    let move_re = Regex::new("move ([0-9]+) from ([0-9]+) to ([0-9]+)").unwrap();
-    let column_nums_re = Regex::new("\\s*([0-9])\\s*").unwrap();
-    let crate_re = Regex::new(" ( )  |\\[([A-Z])\\]").unwrap();
-
-    let lines = input.split('\n').collect::<Vec<&str>>();
-
-    for line in lines.into_iter().rev() {
-        if line.is_empty() { continue }
-        if line.starts_with("move") {
-            let captures = move_re.captures(line).unwrap();
-            let new_move = Move {
-                amount: captures.get(1).unwrap().as_str().parse().unwrap(),
-                from: captures.get(2).unwrap().as_str().parse().unwrap(),
-                to: captures.get(3).unwrap().as_str().parse().unwrap(),
-            };
-            moves.push(new_move);
-        } else if column_nums_re.is_match(line) {
-            for _caps in column_nums_re.captures_iter(line) {
-                stacks.push(Stack::new());
-            }
-        } else if crate_re.is_match(line) {
-            for (idx, caps) in crate_re.captures_iter(line).enumerate() {
-                if let Some(new_crate) = caps.get(2) {
-                    stacks[idx].stack(new_crate.as_str().chars().take(1).collect::<Vec<char>>()[0]);
-                }
-            }
-        }
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let cranes = get_cranes(&get_flag_values(&args, "--crane"));
+    // Modes that use a single crane take the first one given
+    let crane = cranes.first().map(|c| c.as_ref()).unwrap_or(&CrateMover9000);
+
+    if args.iter().any(|a| a == "bench") {
+        bench::run_benchmark(
+            get_flag_number(&args, "--stacks", 9) as usize,
+            get_flag_number(&args, "--height", 100_000) as usize,
+            get_flag_number(&args, "--moves", 1_000_000) as usize,
+            get_flag_number(&args, "--max-amount", 1_000) as usize,
+        );
+        return;
     }
-    moves = moves.into_iter().rev().collect();
 
-    (stacks, moves)
-}
+    let path = get_flag_values(&args, "--input").first().copied().unwrap_or("input.txt");
+    println!("Reading input...");
+    let file_contents = read_to_string(path).unwrap();
 
-fn construct_answer(stacks: &[Stack]) -> String {
-    let mut answer = String::new();
-    for stack in stacks.iter() {
-        answer.push(*stack.read());
+    println!("Parsing input...");
+    let input = match parse_input(&file_contents) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    };
+
+    println!("Checking moves...");
+    if let Err(error) = validate_moves(&input.0, &input.1) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+    if args.iter().any(|a| a == "--check") {
+        println!("All {} moves are valid", input.1.len());
+        return;
     }
 
-    answer
-}
+    if args.iter().any(|a| a == "replay") {
+        let options = replay::ReplayOptions {
+            delay: Duration::from_millis(get_flag_number(&args, "--delay", 200)),
+            paused: args.iter().any(|a| a == "--step"),
+            start_at: get_flag_number(&args, "--from", 0) as usize,
+        };
+        replay::replay(crane, &input.0, &input.1, options);
+        return;
+    }
 
-fn part1(mut stacks: Vec<Stack>, moves: Vec<Move>) -> String {
-    for crane_move in moves.iter() {
-        for _ in 0..crane_move.amount {
-            let handling_crate = stacks[crane_move.from - 1].pop();
-            stacks[crane_move.to - 1].stack(handling_crate);
+    if args.iter().any(|a| a == "plan") {
+        let goal = if let Some(target) = get_flag_values(&args, "--target").first() {
+            planner::Goal::TopCrates(target.to_string())
+        } else if let Some(layout_path) = get_flag_values(&args, "--layout").first() {
+            let layout = read_to_string(layout_path).unwrap();
+            let lines: Vec<&str> = layout.lines().take_while(|l| !l.trim().is_empty()).collect();
+            match parse_drawing(&lines) {
+                Ok(stacks) => planner::Goal::Layout(stacks.iter().map(|s| s.labels().into_iter().map(|l| l.to_string()).collect()).collect()),
+                Err(error) => {
+                    eprintln!("{}: {}", layout_path, error);
+                    process::exit(1);
+                },
+            }
+        } else {
+            eprintln!("plan needs a goal, either --target <top crates> or --layout <drawing file>");
+            process::exit(1);
+        };
+
+        match planner::plan(crane, &input.0, &goal, get_flag_number(&args, "--limit", 100_000) as usize) {
+            Ok(moves) => {
+                println!("{} moves with the {}:", moves.len(), crane.name());
+                for crane_move in moves.iter() {
+                    println!("move {} from {} to {}", crane_move.amount, crane_move.from, crane_move.to);
+                }
+            },
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            },
         }
+        return;
     }
 
-    construct_answer(&stacks)
-}
+    if args.iter().any(|a| a == "provenance") {
+        let trace = provenance::trace(crane, &input.0, &input.1);
 
-fn part2(mut stacks: Vec<Stack>, moves: Vec<Move>) -> String {
-    for crane_move in moves.iter() {
-        let mut handling_crates = Vec::new();
-        for _ in 0..crane_move.amount {
-            handling_crates.push(stacks[crane_move.from - 1].pop());
-        }
-        for handling_crate in handling_crates.into_iter().rev() {
-            stacks[crane_move.to - 1].stack(handling_crate);
+        println!("{}: {}", crane.name(), construct_answer(&trace.stacks));
+        provenance::print_top_origins(&trace);
+        for label in get_flag_values(&args, "--crate") {
+            provenance::print_crate_history(&trace, &input.0, label);
         }
+        return;
     }
 
-    construct_answer(&stacks)
-}
-
-fn main() {
-    println!("Reading input...");
-    let file_contents = read_to_string("input.txt").unwrap();
+    println!("Calculating...");
 
-    println!("Parsing input...");
-    let input = parse_input(&file_contents);
+    if args.iter().any(|a| a == "--draw") {
+        println!("{}\n", render(&input.0));
+        let defaults: [&dyn Crane; 2] = [&CrateMover9000, &CrateMover9001];
+        let drawn: Vec<&dyn Crane> = if cranes.is_empty() { defaults.to_vec() } else { cranes.iter().map(|c| c.as_ref()).collect() };
+        for crane in drawn.into_iter() {
+            println!("{}:\n{}\n", crane.name(), render(&run(crane, &input.0, &input.1)));
+        }
+        return;
+    }
 
-    println!("Calculating...");
+    if !cranes.is_empty() {
+        for crane in cranes.iter() {
+            println!("{}: {}", crane.name(), simulate(crane.as_ref(), &input.0, &input.1));
+        }
+        return;
+    }
 
-    println!("Part 1: {}", part1(input.0.clone(), input.1.clone()));
-    println!("Part 2: {}", part2(input.0.clone(), input.1));
+    println!("Part 1: {}", part1(&input.0, &input.1));
+    println!("Part 2: {}", part2(&input.0, &input.1));
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use day5::{build_stacks, Origin};
 
     fn get_input() -> (Vec<Stack>, Vec<Move>) {
-        parse_input(&read_to_string("input.txt").unwrap())
+        parse_input(&read_to_string("input.txt").unwrap()).unwrap()
     }
 
     #[test]
     fn test_part1() {
         let input = get_input();
-        let res = part1(input.0, input.1);
+        let res = part1(&input.0, &input.1);
         assert_eq!(res, "TGWSMRBPN");
     }
 
     #[test]
     fn test_part2() {
         let input = get_input();
-        let res = part2(input.0, input.1);
+        let res = part2(&input.0, &input.1);
         assert_eq!(res, "TZLTLWRNF");
     }
-}
 
+    #[test]
+    fn test_crane_models() {
+        let stacks = || build_stacks(vec!["EDCBA".chars().map(|c| c.to_string()).collect(), Vec::new()]);
+        let crane_move = Move { amount: 5, from: 1, to: 2, line: 1 };
+        let moved = |crane: &dyn Crane| {
+            let mut stacks = stacks();
+            crane.apply(&mut stacks, &crane_move);
+            stacks[1].labels().concat()
+        };
+
+        assert_eq!(moved(&CrateMover9000), "ABCDE");
+        assert_eq!(moved(&CrateMover9001), "EDCBA");
+        assert_eq!(moved(&crane::CapacityCrane { capacity: 2 }), "BADCE");
+        assert_eq!(moved(&crane::AlternatingCrane), "ADCBE");
+        assert!(crane::get_crane("capacity:0").is_none());
+
+        let (stacks, moves) = parse_input("[C]\n[B]\n[A] [D]\n 1   2\n\nmove 2 from 1 to 1").unwrap();
+        assert_eq!(part1(&stacks, &moves), "CD");
+        assert_eq!(part2(&stacks, &moves), "CD");
+    }
+
+    #[test]
+    fn test_parse_wide_drawing() {
+        let drawing = [
+            "                                       [AB]",
+            "[a]                                [Q] [C]",
+            " 1   2   3   4   5   6   7   8   9  10  11",
+            "",
+            "move 1 from 11 to 1",
+        ].join("\n");
+        let (stacks, moves) = parse_input(&drawing).unwrap();
+
+        assert_eq!(stacks.len(), 11);
+        assert_eq!(stacks[0].labels(), vec!["a"]);
+        assert_eq!(stacks[9].labels(), vec!["Q"]);
+        assert_eq!(stacks[10].labels(), vec!["C", "AB"]);
+        assert_eq!(stacks[10].crates[0].origin, Origin { stack: 11, depth: 1 });
+        assert_eq!(part1(&stacks, &moves), "AB        QC");
+    }
+}
@@ -0,0 +1,220 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, HashMap}, fmt};
+use day5::{construct_answer, crane::Crane, run, Move, Stack};
+
+/// What the stacks should look like once the plan is done
+pub enum Goal {
+    /// The crates on top of the stacks, read left to right like the puzzle answer
+    TopCrates(String),
+    /// The labels of every stack, bottom crate first
+    Layout(Vec<Vec<String>>),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlanError {
+    /// The goal has a different number of stacks or a different set of crates
+    Unreachable,
+    /// Every reachable arrangement was tried and none of them matches the goal
+    NoPlan { explored: usize },
+    NodeLimit { explored: usize },
+}
+
+impl fmt::Display for PlanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlanError::Unreachable => write!(f, "the goal doesn't have the same stacks and crates as the start"),
+            PlanError::NoPlan { explored } => write!(f, "no sequence of moves reaches the goal, tried all {} arrangements", explored),
+            PlanError::NodeLimit { explored } => write!(f, "gave up after finding {} arrangements", explored),
+        }
+    }
+}
+
+/// Marks the end of each stack in a `Key`
+const END_OF_STACK: u32 = u32::MAX;
+
+/// An arrangement by label only: every label as a number, stack after stack. Much smaller to keep
+/// around for every arrangement seen than the stacks themselves.
+type Key = Vec<u32>;
+
+/// How an arrangement was first reached, as the arrangement before it and the move in between.
+/// The stacks themselves aren't kept, they're rebuilt from the moves when needed.
+struct Node {
+    parent: Option<(usize, Move)>,
+}
+
+fn get_key(ids: &HashMap<&str, u32>, stacks: &[Stack]) -> Key {
+    let mut key = Vec::new();
+    for stack in stacks.iter() {
+        key.extend(stack.crates.iter().map(|c| ids[&*c.label]));
+        key.push(END_OF_STACK);
+    }
+
+    key
+}
+
+/// The moves from the start to `node`, in order
+fn get_path(nodes: &[Node], node: usize) -> Vec<Move> {
+    let mut moves = Vec::new();
+    let mut at = node;
+    while let Some((parent, crane_move)) = nodes[at].parent.clone() {
+        moves.push(crane_move);
+        at = parent;
+    }
+    moves.reverse();
+
+    moves
+}
+
+impl Goal {
+    /// The key every matching arrangement has, if the goal pins down every stack
+    fn get_key(&self, ids: &HashMap<&str, u32>) -> Option<Key> {
+        match self {
+            Goal::TopCrates(_) => None,
+            Goal::Layout(layout) => Some(layout.iter()
+                .flat_map(|stack| stack.iter().map(|l| ids[l.as_str()]).chain([END_OF_STACK]))
+                .collect()),
+        }
+    }
+
+    fn is_reached(&self, stacks: &[Stack], key: &[u32], goal_key: Option<&[u32]>) -> bool {
+        match self {
+            Goal::TopCrates(answer) => construct_answer(stacks) == *answer,
+            Goal::Layout(_) => Some(key) == goal_key,
+        }
+    }
+
+    /// A lower bound on the moves still needed. A move only changes two stacks, so it can fix at
+    /// most two of the stacks that are still wrong.
+    fn estimate(&self, stacks: &[Stack], key: &[u32], goal_key: Option<&[u32]>) -> usize {
+        let wrong = match (self, goal_key) {
+            (Goal::TopCrates(answer), _) => {
+                // With labels longer than one letter there's no telling which part of the answer
+                // belongs to which stack
+                let wanted: Vec<String> = answer.chars().map(|c| c.to_string()).collect();
+                if wanted.len() != stacks.len() {
+                    return 0;
+                }
+                stacks.iter().zip(wanted.iter()).filter(|(stack, top)| stack.read() != top.as_str()).count()
+            },
+            (Goal::Layout(_), Some(goal_key)) => {
+                let split = |key: &[u32]| key.split(|id| *id == END_OF_STACK).map(|s| s.to_vec()).collect::<Vec<Vec<u32>>>();
+                split(key).iter().zip(split(goal_key).iter()).filter(|(a, b)| a != b).count()
+            },
+            (Goal::Layout(_), None) => 0,
+        };
+
+        wrong.div_ceil(2)
+    }
+
+    fn is_possible(&self, stacks: &[Stack]) -> bool {
+        match self {
+            Goal::TopCrates(answer) => {
+                // With one letter labels every stack adds exactly one letter to the answer, even
+                // an empty one
+                let single_letters = stacks.iter().flat_map(|s| s.labels()).all(|l| l.chars().count() == 1);
+                !single_letters || answer.chars().count() == stacks.len()
+            },
+            Goal::Layout(layout) => {
+                let mut have: Vec<&str> = stacks.iter().flat_map(|s| s.labels()).collect();
+                let mut want: Vec<&str> = layout.iter().flatten().map(|l| l.as_str()).collect();
+                have.sort_unstable();
+                want.sort_unstable();
+                layout.len() == stacks.len() && have == want
+            },
+        }
+    }
+}
+
+/// Searches for the shortest list of moves that takes `stacks` to `goal` with `crane`, using A*.
+/// Arrangements are compared by label only, so crates with the same label are interchangeable.
+/// Gives up once `node_limit` arrangements have been found, which bounds both time and memory.
+pub fn plan(crane: &dyn Crane, stacks: &[Stack], goal: &Goal, node_limit: usize) -> Result<Vec<Move>, PlanError> {
+    if !goal.is_possible(stacks) {
+        return Err(PlanError::Unreachable);
+    }
+
+    let mut ids: HashMap<&str, u32> = HashMap::new();
+    for label in stacks.iter().flat_map(|s| s.labels()) {
+        let next_id = ids.len() as u32;
+        ids.entry(label).or_insert(next_id);
+    }
+    let goal_key = goal.get_key(&ids);
+    let goal_key = goal_key.as_deref();
+
+    let start_key = get_key(&ids, stacks);
+    let mut nodes = vec![Node { parent: None }];
+    let mut best_steps: HashMap<Key, usize> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    queue.push(Reverse((goal.estimate(stacks, &start_key, goal_key), 0, 0)));
+    best_steps.insert(start_key, 0);
+
+    let mut explored = 0;
+    while let Some(Reverse((_, steps, node))) = queue.pop() {
+        let path = get_path(&nodes, node);
+        let current = run(crane, stacks, &path);
+        let key = get_key(&ids, &current);
+        if best_steps[&key] < steps { continue }
+
+        if goal.is_reached(&current, &key, goal_key) {
+            let mut moves = path;
+            moves.iter_mut().enumerate().for_each(|(idx, m)| m.line = idx + 1);
+            return Ok(moves);
+        }
+        explored += 1;
+
+        for from in 0..current.len() {
+            for to in 0..current.len() {
+                if from == to { continue }
+                for amount in 1..=current[from].crates.len() {
+                    let crane_move = Move { amount: amount as u32, from: from + 1, to: to + 1, line: 0 };
+                    let mut next = current.clone();
+                    crane.apply(&mut next, &crane_move);
+
+                    let next_key = get_key(&ids, &next);
+                    if best_steps.get(&next_key).is_some_and(|best| *best <= steps + 1) { continue }
+                    if nodes.len() >= node_limit {
+                        return Err(PlanError::NodeLimit { explored: nodes.len() });
+                    }
+
+                    let estimate = steps + 1 + goal.estimate(&next, &next_key, goal_key);
+                    best_steps.insert(next_key, steps + 1);
+                    nodes.push(Node { parent: Some((node, crane_move)) });
+                    queue.push(Reverse((estimate, steps + 1, nodes.len() - 1)));
+                }
+            }
+        }
+    }
+
+    Err(PlanError::NoPlan { explored })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day5::{build_stacks, crane::{CrateMover9000, CrateMover9001}, run};
+
+    fn stacks(labels: &[&str]) -> Vec<Stack> {
+        build_stacks(labels.iter().map(|s| s.chars().map(|c| c.to_string()).collect()).collect())
+    }
+
+    #[test]
+    fn test_plan_top_crates() {
+        let start = stacks(&["ZN", "MCD", "P"]);
+        let moves = plan(&CrateMover9000, &start, &Goal::TopCrates("CMZ".to_string()), 100_000).unwrap();
+
+        assert_eq!(construct_answer(&run(&CrateMover9000, &start, &moves)), "CMZ");
+        assert!(moves.len() <= 4);
+        assert!(matches!(plan(&CrateMover9000, &start, &Goal::TopCrates("CM".to_string()), 100_000), Err(PlanError::Unreachable)));
+        assert!(matches!(plan(&CrateMover9000, &start, &Goal::TopCrates("XYZ".to_string()), 10), Err(PlanError::NodeLimit { explored: 10 })));
+    }
+
+    #[test]
+    fn test_plan_layout() {
+        let start = stacks(&["ABC", "", ""]);
+        let goal = Goal::Layout(vec![vec![], vec![], vec!["A".to_string(), "B".to_string(), "C".to_string()]]);
+
+        assert_eq!(plan(&CrateMover9001, &start, &goal, 1_000).unwrap().len(), 1);
+        assert_eq!(plan(&CrateMover9000, &start, &goal, 10_000).unwrap().len(), 2);
+        assert!(matches!(plan(&CrateMover9000, &start, &Goal::Layout(vec![vec![]; 3]), 10), Err(PlanError::Unreachable)));
+        assert!(matches!(plan(&CrateMover9000, &start, &Goal::TopCrates("XYZ".to_string()), 10_000), Err(PlanError::NoPlan { .. })));
+    }
+}
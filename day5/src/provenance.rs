@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use day5::{crane::Crane, Move, Origin, Stack};
+
+/// The final stacks, and for every crate that moved the lines of the moves that carried it
+pub struct Trace {
+    pub stacks: Vec<Stack>,
+    pub carried_by: HashMap<Origin, Vec<usize>>,
+}
+
+impl Trace {
+    /// Where the crate is now: its stack, counting from 1, and how many crates are on top of it
+    pub fn find(&self, origin: &Origin) -> Option<(usize, usize)> {
+        self.stacks.iter().enumerate().find_map(|(idx, stack)| {
+            let level = stack.crates.iter().position(|c| c.origin == *origin)?;
+            Some((idx + 1, stack.crates.len() - 1 - level))
+        })
+    }
+
+    pub fn moves_of(&self, origin: &Origin) -> &[usize] {
+        self.carried_by.get(origin).map(|lines| lines.as_slice()).unwrap_or(&[])
+    }
+}
+
+/// Runs the moves like `run`, recording which crates every move picks up. Which crates a move
+/// picks up doesn't depend on the crane, only the order they're put down in does.
+pub fn trace(crane: &dyn Crane, initial: &[Stack], moves: &[Move]) -> Trace {
+    let mut stacks = initial.to_vec();
+    let mut carried_by: HashMap<Origin, Vec<usize>> = HashMap::new();
+
+    for crane_move in moves.iter() {
+        let source = &stacks[crane_move.from - 1].crates;
+        for carried in source[source.len() - crane_move.amount as usize..].iter() {
+            carried_by.entry(carried.origin).or_default().push(crane_move.line);
+        }
+        crane.apply(&mut stacks, crane_move);
+    }
+
+    Trace {
+        stacks,
+        carried_by,
+    }
+}
+
+fn format_lines(lines: &[usize]) -> String {
+    if lines.is_empty() {
+        return "never moved".to_string();
+    }
+    format!("moved on line {}", lines.iter().map(|l| l.to_string()).collect::<Vec<String>>().join(", "))
+}
+
+/// Prints the crate on top of every stack and where it started
+pub fn print_top_origins(trace: &Trace) {
+    for (idx, stack) in trace.stacks.iter().enumerate() {
+        match stack.crates.last() {
+            Some(top) => println!(
+                "stack {}: [{}] from {}, {}",
+                idx + 1,
+                top.label,
+                top.origin,
+                format_lines(trace.moves_of(&top.origin)),
+            ),
+            None => println!("stack {}: empty", idx + 1),
+        }
+    }
+}
+
+/// Prints the history of every crate labelled `label`
+pub fn print_crate_history(trace: &Trace, initial: &[Stack], label: &str) {
+    let mut found = false;
+    for origin in initial.iter().flat_map(|s| s.crates.iter()).filter(|c| &*c.label == label).map(|c| c.origin) {
+        found = true;
+        let (stack, depth) = trace.find(&origin).unwrap();
+        println!("[{}] from {}, now stack {} depth {}", label, origin, stack, depth);
+        println!("  {}", format_lines(trace.moves_of(&origin)));
+    }
+
+    if !found {
+        println!("No crate labelled [{}]", label);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use day5::{crane::CrateMover9000, parse_input};
+
+    #[test]
+    fn test_trace() {
+        let (stacks, moves) = parse_input("    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\nmove 2 from 2 to 1\nmove 1 from 1 to 2").unwrap();
+        let trace = trace(&CrateMover9000, &stacks, &moves);
+
+        let d = Origin { stack: 2, depth: 0 };
+        assert_eq!(trace.moves_of(&d), &[6, 7]);
+        assert_eq!(trace.find(&d), Some((3, 2)));
+        assert_eq!(trace.moves_of(&Origin { stack: 3, depth: 0 }), &[] as &[usize]);
+        assert_eq!(trace.stacks.iter().map(|s| s.read()).collect::<String>(), "CMZ");
+    }
+}
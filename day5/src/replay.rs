@@ -0,0 +1,117 @@
+use std::{io::{self, BufRead, Write}, sync::mpsc, thread, time::Duration};
+use day5::{crane::Crane, history::History, render, Move, Stack};
+
+pub struct ReplayOptions {
+    pub delay: Duration,
+    pub paused: bool,
+    pub start_at: usize,
+}
+
+/// What the user typed, one command per line
+enum Command {
+    /// Just enter: pause while playing, show the next step while paused
+    Toggle,
+    Play,
+    Jump(usize),
+    Quit,
+}
+
+fn read_commands() -> mpsc::Receiver<Command> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            let command = match line.trim() {
+                "" => Command::Toggle,
+                "p" => Command::Play,
+                "q" => Command::Quit,
+                step => match step.parse() {
+                    Ok(step) => Command::Jump(step),
+                    Err(_) => continue,
+                },
+            };
+            if sender.send(command).is_err() {
+                break;
+            }
+        }
+    });
+
+    receiver
+}
+
+fn draw_frame(stacks: &[Stack], moves: &[Move], step: usize, crane: &dyn Crane, paused: bool) {
+    let description = match step {
+        0 => "starting position".to_string(),
+        _ => {
+            let crane_move = &moves[step - 1];
+            format!("move {} from {} to {}", crane_move.amount, crane_move.from, crane_move.to)
+        },
+    };
+
+    // Clear the screen and go back to the top left corner
+    print!("\x1b[2J\x1b[H");
+    println!("{} | step {}/{}: {}", crane.name(), step, moves.len(), description);
+    println!("{}\n", render(stacks));
+    if paused {
+        println!("Paused. Enter: next step, p: play, <n>: jump to step n, q: quit");
+    } else {
+        println!("Enter: pause, <n>: jump to step n, q: quit");
+    }
+    io::stdout().flush().unwrap();
+}
+
+/// How many moves apart the replay keeps a copy of the stacks, so jumping back doesn't have to
+/// start over from the beginning
+const CHECKPOINT_INTERVAL: usize = 100;
+
+/// Applies the moves one at a time, drawing the stacks after each one
+pub fn replay(crane: &dyn Crane, initial: &[Stack], moves: &[Move], options: ReplayOptions) {
+    let commands = read_commands();
+    let mut paused = options.paused;
+    let mut history = History::with_moves(crane, initial.to_vec(), moves.to_vec(), CHECKPOINT_INTERVAL);
+    let mut target = options.start_at.min(moves.len());
+
+    loop {
+        history.seek(target);
+        let step = history.position();
+        draw_frame(history.current(), moves, step, crane, paused);
+
+        let command = if paused {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        } else {
+            if step == moves.len() {
+                return;
+            }
+            match commands.recv_timeout(options.delay) {
+                Ok(command) => Some(command),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                // Nothing more can be typed once stdin is closed, but playback should keep its pace
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    thread::sleep(options.delay);
+                    None
+                },
+            }
+        };
+
+        match command {
+            Some(Command::Quit) => return,
+            Some(Command::Jump(to)) => {
+                target = to.min(moves.len());
+                paused = true;
+            },
+            Some(Command::Toggle) if paused => {
+                if step == moves.len() {
+                    return;
+                }
+                target = step + 1;
+            },
+            Some(Command::Toggle) => paused = true,
+            Some(Command::Play) => paused = false,
+            None => target = step + 1,
+        }
+    }
+}
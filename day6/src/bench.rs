@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+use crate::{detect_unique_sequence, do_parallel};
+
+/// How `detect_unique_sequence` used to work: shift a buffer along one character at a time and
+/// count every letter in it again at each step. Only here for the benchmark to measure against.
+fn detect_by_rescanning(data: &[char], sequence_length: usize) -> Result<usize, String> {
+    let mut buffer: Vec<char> = vec!['a'];
+    buffer.extend(data.iter().take(sequence_length - 1).copied());
+    let mut skip_check: usize = 0;
+    'outer: for (idx, character) in data.iter().enumerate().skip(sequence_length - 1) {
+        buffer.push(*character);
+        buffer.remove(0);
+        if skip_check > 0 {
+            skip_check -= 1;
+            continue;
+        }
+
+        for (letter_idx, letter) in buffer.iter().take(sequence_length - 1).enumerate() {
+            let mut total_of_letter = 0;
+            buffer.iter().for_each(|l| if l == letter { total_of_letter += 1});
+            if total_of_letter > 1 {
+                skip_check = letter_idx;
+                continue 'outer;
+            }
+        }
+
+        return Ok(idx + 1);
+    }
+
+    Err("Not found".to_string())
+}
+
+/// A signal of `length` characters with its one marker of `sequence_length` right at the end. The
+/// rest only uses `sequence_length - 1` different letters, so no window before the end can be a
+/// marker and every detector has to read all of it. The letters are mixed up a bit so a detector
+/// can't coast along a repeating pattern.
+fn generate(length: usize, sequence_length: usize) -> Vec<char> {
+    let letters: Vec<char> = ('a'..='z').chain('A'..='Z').take(sequence_length).collect();
+
+    let filler = length.saturating_sub(sequence_length);
+    let mut data: Vec<char> = (0..filler).map(|i| letters[(i * i + i / 3) % (sequence_length - 1)]).collect();
+    data.extend(letters.iter());
+
+    data
+}
+
+/// Runs each detector over the same generated signal and prints how long it took next to the
+/// speed-up over the original
+pub fn run_benchmark(length: usize, sequence_length: usize, workers: usize) {
+    if !(2..=52).contains(&sequence_length) {
+        eprintln!("The benchmark needs a window of 2 to 52 characters");
+        return;
+    }
+    println!("Generating {} characters with a marker of {} at the end...", length, sequence_length);
+    let data = generate(length, sequence_length);
+
+    let mut baseline: Option<Duration> = None;
+    let mut measure = |name: &str, detector: &dyn Fn() -> Result<usize, String>| {
+        let start = Instant::now();
+        let result = detector();
+        let elapsed = start.elapsed();
+
+        assert_eq!(result, Ok(data.len()), "{} missed the marker at the end", name);
+        let speed_up = baseline.get_or_insert(elapsed).as_secs_f64() / elapsed.as_secs_f64();
+        println!("{:<22} {:>10.2?} ({:.1}x)", name, elapsed, speed_up);
+    };
+
+    measure("Rescanning", &|| detect_by_rescanning(&data, sequence_length));
+    measure("Single pass", &|| detect_unique_sequence(&data, sequence_length));
+    measure("Single pass, threaded", &|| do_parallel(&data, sequence_length, workers));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detectors_agree() {
+        for sequence_length in [2, 4, 14] {
+            let data = generate(5_000, sequence_length);
+            assert_eq!(detect_unique_sequence(&data, sequence_length), Ok(5_000));
+            assert_eq!(detect_by_rescanning(&data, sequence_length), Ok(5_000));
+        }
+
+        let data: Vec<char> = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect();
+        for sequence_length in 1..=14 {
+            assert_eq!(detect_unique_sequence(&data, sequence_length), detect_by_rescanning(&data, sequence_length));
+        }
+    }
+}
@@ -1,155 +1,219 @@
-use std::{fs::read_to_string, thread, sync::Arc};
+mod bench;
+mod markers;
+mod stream;
 
-const MAX_THREADS: usize = 16;
+use std::{collections::HashMap, fs::{read_to_string, File}, io, mem, process, thread};
 
-fn detect_unique_sequence(data: &[char], sequence_length: usize) -> Result<usize, String> {
-    let mut buffer: Vec<char> = vec!['a'];
-    buffer.append(&mut data.iter().take(sequence_length - 1).map(|c| *c).collect::<Vec<char>>());
-    let mut skip_check: usize = 0;
-    'outer: for (idx, character) in data.iter().enumerate().skip(sequence_length - 1) {
-        buffer.push(*character);
-        buffer.remove(0);
-        if skip_check > 0 {
-            skip_check -= 1;
-            continue;
-        }
+const DEFAULT_WORKERS: usize = 16;
 
-        for (letter_idx, letter) in buffer.iter().take(sequence_length - 1).enumerate() {
-            let mut total_of_letter = 0;
-            buffer.iter().for_each(|l| if l == letter { total_of_letter += 1});
-            if total_of_letter > 1 {
-                skip_check = letter_idx;
-                continue 'outer;
-            }
-        }
+/// Where each character was last seen, as the position just after it so 0 means never. Characters
+/// below 256 get a plain table, anything else goes in a map.
+struct LastSeen {
+    table: [usize; 256],
+    others: HashMap<char, usize>,
+}
 
-        // println!("{} {} {}", skip, character, idx + skip + 1);
-        return Ok(idx + 1);
+impl LastSeen {
+    fn new() -> Self {
+        LastSeen { table: [0; 256], others: HashMap::new() }
     }
 
-    Err("Not found".to_string())
+    /// Records that `character` ends at `end`, returning where it ended the time before
+    fn replace(&mut self, character: char, end: usize) -> usize {
+        match self.table.get_mut(character as usize) {
+            Some(seen) => mem::replace(seen, end),
+            None => self.others.insert(character, end).unwrap_or(0),
+        }
+    }
 }
 
-fn do_parallel(data: &[char], sequence_length: usize) -> usize {
-    let mut threads = Vec::new();
-    let data_arc: Arc<Vec<char>> = Arc::new(data.iter().map(|c| *c).collect::<Vec<char>>());
-
-    let mut number_of_threads = MAX_THREADS;
-    let mut section_size = data.len() / MAX_THREADS;
-    while section_size < sequence_length {
-        number_of_threads -= 1;
-        section_size = data.len() / number_of_threads;
-    }
-
-    // println!("{:?}", data);
-    for i in 0..number_of_threads {
-        // let start = ((i * section_size as i32) - (sequence_length - 1) as i32).max(0) as usize;
-        let start = ((i * section_size) as i32 - (sequence_length - 1) as i32).max(0) as usize;
-        let sec_size = ((i * section_size) as usize + section_size) - start;
-        let data_arc_clone = Arc::clone(&data_arc);
-        let seq_length = sequence_length.clone();
-        // println!("{} {:?}", start, data_arc_clone.iter().skip(start).take(sec_size).map(|c| *c).collect::<Vec<char>>());
-        threads.push(thread::spawn(move || {
-            let res = detect_unique_sequence(&data_arc_clone[start..start+sec_size], seq_length);
-            if let Ok(n) = res {
-                return Ok(n + start)
-            }
-            Err("Not found")
-        }));
-    }
-
-    let mut lowest = None;
-    for join_handle in threads.into_iter() {
-        let result = join_handle.join().unwrap();
-
-        if let Ok(num) = result {
-            if let Some(n) = lowest {
-                if num < n {
-                    lowest = Some(num);
-                }
-            } else {
-                lowest = Some(num);
-            }
+/// Finds the first position where the last `sequence_length` characters are all different, in a
+/// single pass. Every character only needs to be checked against the last time it was seen.
+fn detect_unique_sequence(data: &[char], sequence_length: usize) -> Result<usize, String> {
+    let mut last_seen = LastSeen::new();
+    // Where the run of distinct characters ending at the current one starts
+    let mut run_start = 0;
+
+    for (idx, character) in data.iter().enumerate() {
+        run_start = run_start.max(last_seen.replace(*character, idx + 1));
+        if idx + 1 - run_start >= sequence_length {
+            return Ok(idx + 1);
         }
     }
 
-    if let Some(num) = lowest {
-        return num;
+    Err("Not found".to_string())
+}
+
+/// Splits `data` into one section per worker and searches them all at once. Every section but the
+/// first also starts with the `sequence_length - 1` characters before it, so a marker that
+/// straddles two sections is still found. With one worker, or sections too short to hold a
+/// marker, it's quicker to search the whole thing in one go.
+fn do_parallel(data: &[char], sequence_length: usize, workers: usize) -> Result<usize, String> {
+    let section_size = data.len().div_ceil(workers.max(1));
+    if workers <= 1 || section_size == 0 || section_size < sequence_length {
+        return detect_unique_sequence(data, sequence_length);
     }
 
-    panic!("Not found");
+    let overlap = sequence_length.saturating_sub(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..data.len()).step_by(section_size)
+            .map(|section_start| {
+                let start = section_start.saturating_sub(overlap);
+                let end = (section_start + section_size).min(data.len());
+                scope.spawn(move || detect_unique_sequence(&data[start..end], sequence_length).map(|n| n + start))
+            })
+            .collect();
+
+        // Sections are in order, so the first one with a marker has the earliest
+        handles.into_iter()
+            .map(|handle| handle.join().unwrap())
+            .find(|result| result.is_ok())
+            .unwrap_or_else(|| Err("Not found".to_string()))
+    })
+}
+
+fn part1(data: &[char], workers: usize) -> usize {
+    do_parallel(data, 4, workers).unwrap()
+}
+
+fn part2(data: &[char], workers: usize) -> usize {
+    do_parallel(data, 14, workers).unwrap()
 }
 
-fn part1(data: &[char]) -> usize {
-    do_parallel(data, 4)
+/// The value given for `flag`, as in `--flag value`
+fn get_flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let idx = args.iter().position(|a| a == flag)?;
+    args.get(idx + 1).map(|value| value.as_str())
 }
 
-fn part2(data: &[char]) -> usize {
-    do_parallel(&data, 14)
+fn get_flag_number(args: &[String], flag: &str, default: usize) -> usize {
+    match get_flag_value(args, flag) {
+        Some(value) => value.parse().unwrap_or_else(|_| {
+            eprintln!("Expected a number for {}, found '{}'", flag, value);
+            process::exit(1);
+        }),
+        None => default,
+    }
 }
 
 fn main() {
-    let file_contents = read_to_string("bigboy.txt").unwrap();
+    let args: Vec<String> = std::env::args().collect();
+    let workers = get_flag_number(&args, "--workers", DEFAULT_WORKERS);
+    if args.iter().any(|a| a == "bench") {
+        bench::run_benchmark(get_flag_number(&args, "--length", 10_000_000), get_flag_number(&args, "--window", 14), workers);
+        return;
+    }
+
+    let path = get_flag_value(&args, "--input");
+    let sequence_length = get_flag_number(&args, "--window", 14);
+    if sequence_length == 0 && args.iter().any(|a| a == "stream" || a == "markers") {
+        eprintln!("--window has to be at least 1");
+        return;
+    }
+    if args.iter().any(|a| a == "stream") {
+        // Without a file, read whatever is piped in
+        let result = match path {
+            Some(path) => File::open(path).and_then(|file| stream::detect_in_stream(file, sequence_length)),
+            None => stream::detect_in_stream(io::stdin().lock(), sequence_length),
+        };
+        match result {
+            Ok(Some(offset)) => println!("Marker of {} found after {} bytes", sequence_length, offset),
+            Ok(None) => println!("No marker of {} found", sequence_length),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    let file_contents = read_to_string(path.unwrap_or("bigboy.txt")).unwrap();
     let data = file_contents.trim();
     let chars = data.chars().collect::<Vec<char>>();
-    let result_1 = part1(&chars);
+
+    if args.iter().any(|a| a == "markers") {
+        let report = markers::find_markers(&chars, sequence_length, args.iter().any(|a| a == "--non-overlapping"));
+        markers::print_report(&chars, sequence_length, &report);
+        return;
+    }
+
+    let result_1 = part1(&chars, workers);
 
     println!("Part 1: {}", result_1);
-    let result_2 = part2(&chars);
+    let result_2 = part2(&chars, workers);
     println!("Part 2: {}", result_2);
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn part_1_test_1() {
-        assert_eq!(part1(&"mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect::<Vec<char>>()), 7);
+        assert_eq!(part1(&"mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 7);
     }
 
     #[test]
     fn part_1_test_2() {
-        assert_eq!(part1(&"bvwbjplbgvbhsrlpgdmjqwftvncz".chars().collect::<Vec<char>>()), 5);
+        assert_eq!(part1(&"bvwbjplbgvbhsrlpgdmjqwftvncz".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 5);
     }
 
     #[test]
     fn part_1_test_3() {
-        assert_eq!(part1(&"nppdvjthqldpwncqszvftbrmjlhg".chars().collect::<Vec<char>>()), 6);
+        assert_eq!(part1(&"nppdvjthqldpwncqszvftbrmjlhg".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 6);
     }
 
     #[test]
     fn part_1_test_4() {
-        assert_eq!(part1(&"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars().collect::<Vec<char>>()), 10);
+        assert_eq!(part1(&"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 10);
     }
 
     #[test]
     fn part_1_test_5() {
-        assert_eq!(part1(&"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars().collect::<Vec<char>>()), 11);
+        assert_eq!(part1(&"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 11);
     }
 
     #[test]
     fn part_2_test_1() {
-        assert_eq!(part2(&"mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect::<Vec<char>>()), 19);
+        assert_eq!(part2(&"mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 19);
     }
 
     #[test]
     fn part_2_test_2() {
-        assert_eq!(part2(&"bvwbjplbgvbhsrlpgdmjqwftvncz".chars().collect::<Vec<char>>()), 23);
+        assert_eq!(part2(&"bvwbjplbgvbhsrlpgdmjqwftvncz".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 23);
     }
 
     #[test]
     fn part_2_test_3() {
-        assert_eq!(part2(&"nppdvjthqldpwncqszvftbrmjlhg".chars().collect::<Vec<char>>()), 23);
+        assert_eq!(part2(&"nppdvjthqldpwncqszvftbrmjlhg".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 23);
     }
 
     #[test]
     fn part_2_test_4() {
-        assert_eq!(part2(&"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars().collect::<Vec<char>>()), 29);
+        assert_eq!(part2(&"nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 29);
     }
 
     #[test]
     fn part_2_test_5() {
-        assert_eq!(part2(&"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars().collect::<Vec<char>>()), 26);
+        assert_eq!(part2(&"zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".chars().collect::<Vec<char>>(), DEFAULT_WORKERS), 26);
+    }
+
+    #[test]
+    fn test_marker_in_last_section() {
+        // 16 sections of 1 with 3 left over, the marker is only in the left over part
+        let data: Vec<char> = "ababababababababababcd".chars().collect();
+        assert_eq!(do_parallel(&data, 4, 16), Ok(22));
+        assert_eq!(do_parallel(&data, 4, 7), Ok(22));
+        assert_eq!(do_parallel(&data[..3], 4, 16), Err("Not found".to_string()));
+        assert_eq!(do_parallel(&[], 4, 0), Err("Not found".to_string()));
+    }
+
+    proptest! {
+        #[test]
+        fn parallel_matches_single_threaded(data in "[a-f]{0,300}", sequence_length in 0usize..7, workers in 0usize..40) {
+            let data: Vec<char> = data.chars().collect();
+            prop_assert_eq!(do_parallel(&data, sequence_length, workers), detect_unique_sequence(&data, sequence_length));
+        }
     }
 }
@@ -0,0 +1,100 @@
+use crate::LastSeen;
+
+#[derive(Debug, PartialEq)]
+pub struct Marker {
+    /// The position just after the marker, like the puzzle answer
+    pub end: usize,
+    pub contents: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MarkerReport {
+    pub markers: Vec<Marker>,
+    /// The (start, end) of the first of the longest runs of distinct characters, end exclusive
+    pub longest_run: (usize, usize),
+}
+
+/// Finds every position where the last `sequence_length` characters are all different. With
+/// `non_overlapping` set, a marker only counts if it starts after the previous one ended.
+pub fn find_markers(data: &[char], sequence_length: usize, non_overlapping: bool) -> MarkerReport {
+    let mut last_seen = LastSeen::new();
+    let mut run_start = 0;
+    // Where the next marker may start, which moves past each marker when they can't overlap
+    let mut free_from = 0;
+    let mut markers = Vec::new();
+    let mut longest_run = (0, 0);
+
+    for (idx, character) in data.iter().enumerate() {
+        let end = idx + 1;
+        run_start = run_start.max(last_seen.replace(*character, end));
+        if end - run_start > longest_run.1 - longest_run.0 {
+            longest_run = (run_start, end);
+        }
+
+        if sequence_length > 0 && end - run_start.max(free_from) >= sequence_length {
+            markers.push(Marker { end, contents: data[end - sequence_length..end].iter().collect() });
+            if non_overlapping {
+                free_from = end;
+            }
+        }
+    }
+
+    MarkerReport { markers, longest_run }
+}
+
+pub fn print_report(data: &[char], sequence_length: usize, report: &MarkerReport) {
+    for marker in report.markers.iter() {
+        println!("{:>10}: {}", marker.end, marker.contents);
+    }
+
+    let positions = (data.len() + 1).saturating_sub(sequence_length);
+    println!("{} markers of {} in {} positions", report.markers.len(), sequence_length, positions);
+    let (start, end) = report.longest_run;
+    println!("Longest run of distinct characters: {} at {}..{}: {}", end - start, start, end, data[start..end].iter().collect::<String>());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn is_distinct(window: &[char]) -> bool {
+        window.iter().enumerate().all(|(idx, c)| !window[idx + 1..].contains(c))
+    }
+
+    #[test]
+    fn test_find_markers() {
+        let data: Vec<char> = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".chars().collect();
+        let report = find_markers(&data, 14, false);
+        assert_eq!(report.markers[0], Marker { end: 19, contents: "qmgbljsphdztnv".to_string() });
+
+        let non_overlapping = find_markers(&data, 4, true);
+        let ends: Vec<usize> = non_overlapping.markers.iter().map(|m| m.end).collect();
+        assert_eq!(&ends[..3], &[7, 11, 15]);
+    }
+
+    proptest! {
+        #[test]
+        fn markers_match_brute_force(data in "[a-e]{0,100}", sequence_length in 1usize..6) {
+            let data: Vec<char> = data.chars().collect();
+            let report = find_markers(&data, sequence_length, false);
+
+            let expected: Vec<usize> = data.windows(sequence_length).enumerate()
+                .filter(|(_, window)| is_distinct(window))
+                .map(|(start, _)| start + sequence_length)
+                .collect();
+            prop_assert_eq!(report.markers.iter().map(|m| m.end).collect::<Vec<usize>>(), expected);
+
+            let longest = (0..=data.len()).flat_map(|end| (0..=end).map(move |start| (start, end)))
+                .filter(|(start, end)| is_distinct(&data[*start..*end]))
+                .map(|(start, end)| end - start)
+                .max()
+                .unwrap();
+            prop_assert_eq!(report.longest_run.1 - report.longest_run.0, longest);
+
+            let non_overlapping = find_markers(&data, sequence_length, true);
+            prop_assert!(non_overlapping.markers.windows(2).all(|pair| pair[1].end - pair[0].end >= sequence_length));
+            prop_assert_eq!(non_overlapping.markers.first(), report.markers.first());
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, Read};
+
+/// How much is read from the source at a time
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Looks for a marker one byte at a time. Only where each byte value was last seen is kept, so the
+/// memory needed is the same however long the stream is. Every byte counts, line breaks included, so
+/// offsets are positions in the file. Unlike the normal path, a trailing newline isn't trimmed, so it
+/// can complete a marker at the very end. Offsets count bytes, so they only match the answers for
+/// ASCII input.
+pub struct StreamDetector {
+    sequence_length: u64,
+    /// The position just after the last time each byte was seen, 0 for never
+    last_seen: [u64; 256],
+    /// Where the run of distinct bytes ending at the last one starts
+    run_start: u64,
+    position: u64,
+}
+
+impl StreamDetector {
+    pub fn new(sequence_length: usize) -> Self {
+        StreamDetector {
+            sequence_length: sequence_length as u64,
+            last_seen: [0; 256],
+            run_start: 0,
+            position: 0,
+        }
+    }
+
+    /// Takes the next byte, returning how many bytes have been read if it completes a marker
+    pub fn push(&mut self, byte: u8) -> Option<u64> {
+        self.position += 1;
+        let seen = &mut self.last_seen[byte as usize];
+        self.run_start = self.run_start.max(*seen);
+        *seen = self.position;
+
+        (self.position - self.run_start >= self.sequence_length).then_some(self.position)
+    }
+}
+
+/// Reads `source` until the first marker of `sequence_length` distinct bytes, returning the
+/// offset just after it without reading any further
+pub fn detect_in_stream(mut source: impl Read, sequence_length: usize) -> io::Result<Option<u64>> {
+    let mut detector = StreamDetector::new(sequence_length);
+    let mut chunk = [0; CHUNK_SIZE];
+
+    loop {
+        let read = match source.read(&mut chunk) {
+            Ok(0) => return Ok(None),
+            Ok(read) => read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        if let Some(offset) = chunk[..read].iter().find_map(|byte| detector.push(*byte)) {
+            return Ok(Some(offset));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::detect_unique_sequence;
+
+    #[test]
+    fn test_detect_in_stream() {
+        for example in ["mjqjpqmgbljsphdztnvjfqwrcgsmlb", "nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", "aaaa"] {
+            let chars: Vec<char> = example.chars().collect();
+            for sequence_length in [4, 14] {
+                let expected = detect_unique_sequence(&chars, sequence_length).ok().map(|n| n as u64);
+                assert_eq!(detect_in_stream(example.as_bytes(), sequence_length).unwrap(), expected);
+            }
+        }
+
+        // Line breaks are bytes like any other, wherever they are, as on the normal path
+        let chars: Vec<char> = "abcab\naxyz".chars().collect();
+        assert_eq!(detect_unique_sequence(&chars, 4), Ok(6));
+        assert_eq!(detect_in_stream(&b"abcab\naxyz"[..], 4).unwrap(), Some(6));
+        assert_eq!(detect_in_stream(&b"abc\n"[..], 4).unwrap(), Some(4));
+
+        // A marker well past the first chunk
+        let source = io::repeat(b'x').take(1_000_000).chain(&b"xabcd"[..]);
+        assert_eq!(detect_in_stream(source, 5).unwrap(), Some(1_000_005));
+    }
+}
@@ -1,4 +1,5 @@
 use std::{fs::read_to_string};
+use log::debug;
 
 const DISK_SPACE: u32 = 70_000_000;
 const REQUIRED_SPACE: u32 = 30_000_000;
@@ -18,8 +19,8 @@ enum DiskItem {
 impl DiskItem {
     pub fn get_size(&self) -> u32 {
         match self {
-            DiskItem::File { name, size } => *size,
-            DiskItem::Folder { name, children } => {
+            DiskItem::File { size, .. } => *size,
+            DiskItem::Folder { children, .. } => {
                 children.iter().map(|c| c.get_size()).sum()
             },
         }
@@ -27,31 +28,31 @@ impl DiskItem {
 
     pub fn get_name(&self) -> String {
         match self {
-            DiskItem::File { name, size } => name.clone(),
-            DiskItem::Folder { name, children } => name.clone(),
+            DiskItem::File { name, .. } => name.clone(),
+            DiskItem::Folder { name, .. } => name.clone(),
         }
     }
 
     pub fn add_child(&mut self, new_child: DiskItem) {
         match self {
-            DiskItem::Folder { name, children } => {
+            DiskItem::Folder { children, .. } => {
                 children.push(new_child);
             },
-            DiskItem::File { name, size } => panic!("Cannot add child to file"),
+            DiskItem::File { .. } => panic!("Cannot add child to file"),
         }
     }
 
     pub fn get_child(&mut self, child_name: &str) -> &mut DiskItem {
         match self {
-            DiskItem::Folder { name, children } => {
+            DiskItem::Folder { children, .. } => {
                 for child in children.iter_mut() {
-                    if &child.get_name() == child_name {
+                    if child.get_name() == child_name {
                         return child;
                     }
                 }
                 panic!("No child found with name '{}'", child_name);
             },
-            DiskItem::File { name, size } => panic!("Cannot get child from file"),
+            DiskItem::File { .. } => panic!("Cannot get child from file"),
         }
     }
 }
@@ -105,7 +106,7 @@ fn parse_input(input: &str) -> DiskItem {
 
 fn get_sum_of_matching_folders(disk_item: &DiskItem, max_size: u32) -> Vec<DiskItem> {
     match disk_item {
-        DiskItem::Folder { name, children } => {
+        DiskItem::Folder { children, .. } => {
             let mut all_matching_folders: Vec<DiskItem> = Vec::new();
 
             for child in children.iter() {
@@ -119,7 +120,7 @@ fn get_sum_of_matching_folders(disk_item: &DiskItem, max_size: u32) -> Vec<DiskI
 
             all_matching_folders
         },
-        DiskItem::File { name, size } => Vec::new(),
+        DiskItem::File { .. } => Vec::new(),
     }
 }
 
@@ -133,15 +134,26 @@ fn part2(root: DiskItem) -> u32 {
     let free_space = DISK_SPACE - root.get_size();
     let space_to_free = REQUIRED_SPACE - free_space;
     let mut all_matching_folders = get_sum_of_matching_folders(&root, u32::MAX);
-    all_matching_folders = all_matching_folders.into_iter().filter(|f| f.get_size() > space_to_free).collect();
+    all_matching_folders.retain(|f| f.get_size() > space_to_free);
 
     all_matching_folders.sort_by_key(|f| f.get_size());
 
-    println!("{:?}", all_matching_folders);
+    for folder in all_matching_folders.iter() {
+        debug!("{} ({})", folder.get_name(), folder.get_size());
+    }
     all_matching_folders[0].get_size()
 }
 
+/// Logs to stderr at `warn` by default, or `debug` with `-v`/`--verbose`. `RUST_LOG` overrides both.
+fn init_logging(args: &[String]) {
+    let default_level = if args.iter().any(|a| a == "--verbose" || a == "-v") { "debug" } else { "warn" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level)).init();
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    init_logging(&args);
+
     let file_contents = read_to_string("input.txt").unwrap();
     let root = parse_input(&file_contents);
 